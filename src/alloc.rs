@@ -2,6 +2,7 @@
 // WASM-4: https://wasm4.org/docs
 
 use buddy_alloc::{BuddyAllocParam, FastAllocParam, NonThreadsafeAlloc};
+use core::ptr::addr_of;
 
 // These values can be tuned
 const FAST_HEAP_SIZE: usize = 4 * 1024; // 4 KB
@@ -12,8 +13,8 @@ static mut FAST_HEAP: [u8; FAST_HEAP_SIZE] = [0u8; FAST_HEAP_SIZE];
 static mut HEAP: [u8; HEAP_SIZE] = [0u8; HEAP_SIZE];
 
 #[global_allocator]
-static ALLOC: NonThreadsafeAlloc = unsafe {
-    let fast_param = FastAllocParam::new(FAST_HEAP.as_ptr(), FAST_HEAP_SIZE);
-    let buddy_param = BuddyAllocParam::new(HEAP.as_ptr(), HEAP_SIZE, LEAF_SIZE);
+static ALLOC: NonThreadsafeAlloc = {
+    let fast_param = FastAllocParam::new(addr_of!(FAST_HEAP).cast(), FAST_HEAP_SIZE);
+    let buddy_param = BuddyAllocParam::new(addr_of!(HEAP).cast(), HEAP_SIZE, LEAF_SIZE);
     NonThreadsafeAlloc::new(fast_param, buddy_param)
 };
@@ -0,0 +1,279 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Runs the game loop without WASM-4, feeding it scripted gamepad input and
+//! dumping frames as images.
+//!
+//! Usage: simulator [--frames N] [--input SCRIPT] [--replay FILE]
+//!                  [--disk FILE] [--every N] [--out PREFIX]
+//!                  [--format png|ppm]
+//!
+//! An input script has one entry per line, `<frame> [buttons...]`, where the
+//! frame may also be given in seconds (`2s`) and the buttons are any of
+//! `x`, `z`, `left`, `right`, `up` and `down`. Buttons are pressed on the
+//! first gamepad unless prefixed by a player number, as in `2:left`. Each
+//! entry holds until the next one. Empty lines and lines starting with `#`
+//! are ignored.
+//!
+//! A replay file contains the hex string that the game traces as `REPLAY`
+//! when a run ends; the simulator then starts by playing back that run.
+//!
+//! The disk file, if given, is loaded before the first frame and written
+//! back after the last one, so save data persists across simulations.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main()
+{
+	if let Err(message) = simulator::run(std::env::args().skip(1))
+	{
+		eprintln!("simulator: {}", message);
+		std::process::exit(1);
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod simulator
+{
+	use cart::headless::Headless;
+	use cart::replay::Recording;
+	use cart::Game;
+	use cart::{
+		BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP,
+		MAX_PLAYERS,
+	};
+
+	const TICKS_PER_SECOND: u32 = 60;
+
+	enum Format
+	{
+		Png,
+		Ppm,
+	}
+
+	struct Options
+	{
+		frames: u32,
+		script: Vec<(u32, [u8; MAX_PLAYERS])>,
+		replay: Option<Recording>,
+		disk: Option<String>,
+		every: u32,
+		out: String,
+		format: Format,
+	}
+
+	pub fn run(args: impl Iterator<Item = String>) -> Result<(), String>
+	{
+		let options = parse_options(args)?;
+
+		let mut game = match &options.replay
+		{
+			Some(recording) => Game::replay(recording),
+			None => Game::new(),
+		};
+		let mut console = Headless::new();
+		if let Some(filename) = &options.disk
+		{
+			console.disk = std::fs::read(filename).unwrap_or_default();
+		}
+		game.start(&mut console);
+
+		let mut next_entry = 0;
+		for frame in 0..options.frames
+		{
+			while next_entry < options.script.len()
+				&& options.script[next_entry].0 <= frame
+			{
+				console.gamepads = options.script[next_entry].1;
+				next_entry += 1;
+			}
+
+			console.clear();
+			game.update(&mut console);
+
+			for line in console.traces.drain(..)
+			{
+				println!("{}", line);
+			}
+
+			let is_last = frame + 1 == options.frames;
+			let is_due = options.every > 0 && (frame + 1) % options.every == 0;
+			if is_last || is_due
+			{
+				dump(&console, &options, frame + 1)?;
+			}
+		}
+
+		if let Some(filename) = &options.disk
+		{
+			std::fs::write(filename, &console.disk).map_err(|error| {
+				format!("cannot write {}: {}", filename, error)
+			})?;
+		}
+		Ok(())
+	}
+
+	fn dump(
+		console: &Headless,
+		options: &Options,
+		frame: u32,
+	) -> Result<(), String>
+	{
+		let (extension, data) = match options.format
+		{
+			Format::Png => ("png", console.to_png()),
+			Format::Ppm => ("ppm", console.to_ppm()),
+		};
+		let filename = format!("{}_{:05}.{}", options.out, frame, extension);
+		std::fs::write(&filename, data)
+			.map_err(|error| format!("cannot write {}: {}", filename, error))
+	}
+
+	fn parse_options(
+		mut args: impl Iterator<Item = String>,
+	) -> Result<Options, String>
+	{
+		let mut options = Options {
+			frames: 10 * TICKS_PER_SECOND,
+			script: Vec::new(),
+			replay: None,
+			disk: None,
+			every: 0,
+			out: "frame".to_string(),
+			format: Format::Png,
+		};
+		while let Some(arg) = args.next()
+		{
+			let mut value = || {
+				args.next()
+					.ok_or_else(|| format!("missing value for {}", arg))
+			};
+			match arg.as_str()
+			{
+				"--frames" => options.frames = parse_number(&value()?)?,
+				"--every" => options.every = parse_number(&value()?)?,
+				"--out" => options.out = value()?,
+				"--disk" => options.disk = Some(value()?),
+				"--input" =>
+				{
+					let filename = value()?;
+					let script = std::fs::read_to_string(&filename).map_err(
+						|error| format!("cannot read {}: {}", filename, error),
+					)?;
+					options.script = parse_script(&script)?;
+				}
+				"--replay" =>
+				{
+					let filename = value()?;
+					let text = std::fs::read_to_string(&filename).map_err(
+						|error| format!("cannot read {}: {}", filename, error),
+					)?;
+					let text = text.trim().trim_start_matches("REPLAY");
+					let recording =
+						Recording::from_hex(text).ok_or_else(|| {
+							format!("invalid replay {}", filename)
+						})?;
+					options.replay = Some(recording);
+				}
+				"--format" =>
+				{
+					options.format = match value()?.as_str()
+					{
+						"png" => Format::Png,
+						"ppm" => Format::Ppm,
+						other =>
+						{
+							return Err(format!("unknown format {}", other))
+						}
+					}
+				}
+				_ => return Err(format!("unknown argument {}", arg)),
+			}
+		}
+		Ok(options)
+	}
+
+	fn parse_script(
+		script: &str,
+	) -> Result<Vec<(u32, [u8; MAX_PLAYERS])>, String>
+	{
+		let mut entries: Vec<(u32, [u8; MAX_PLAYERS])> = Vec::new();
+		for (i, line) in script.lines().enumerate()
+		{
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#')
+			{
+				continue;
+			}
+			let mut words = line.split_whitespace();
+			let frame = words.next().map(parse_frame).unwrap_or(Ok(0))?;
+			let mut gamepads = [0; MAX_PLAYERS];
+			for word in words
+			{
+				let (player, button) = match word.split_once(':')
+				{
+					Some((number, button)) => match number.parse::<usize>()
+					{
+						Ok(n) if (1..=MAX_PLAYERS).contains(&n) =>
+						{
+							(n - 1, button)
+						}
+						_ =>
+						{
+							return Err(format!(
+								"line {}: unknown player {}",
+								i + 1,
+								number
+							))
+						}
+					},
+					None => (0, word),
+				};
+				gamepads[player] |= match button
+				{
+					"-" => 0,
+					"x" => BUTTON_1,
+					"z" => BUTTON_2,
+					"left" => BUTTON_LEFT,
+					"right" => BUTTON_RIGHT,
+					"up" => BUTTON_UP,
+					"down" => BUTTON_DOWN,
+					_ =>
+					{
+						return Err(format!(
+							"line {}: unknown button {}",
+							i + 1,
+							word
+						))
+					}
+				};
+			}
+			if entries.last().is_some_and(|&(last, _)| frame < last)
+			{
+				return Err(format!("line {}: frames out of order", i + 1));
+			}
+			entries.push((frame, gamepads));
+		}
+		Ok(entries)
+	}
+
+	fn parse_frame(word: &str) -> Result<u32, String>
+	{
+		match word.strip_suffix('s')
+		{
+			Some(seconds) => Ok(parse_number(seconds)? * TICKS_PER_SECOND),
+			None => parse_number(word),
+		}
+	}
+
+	fn parse_number(word: &str) -> Result<u32, String>
+	{
+		word.parse()
+			.map_err(|_| format!("expected a number, got {}", word))
+	}
+}
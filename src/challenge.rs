@@ -0,0 +1,124 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::Input;
+use crate::level::Level;
+use crate::menu;
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::seed_code::CODE_LENGTH;
+use crate::wasm4::*;
+use crate::{Context, Scene, Transition};
+
+/// Enters a seed code with the d-pad, so that everyone who enters the same
+/// code dodges the same balls.
+pub struct Challenge
+{
+	cursor: usize,
+}
+
+impl Challenge
+{
+	pub const fn new() -> Self
+	{
+		Self { cursor: 0 }
+	}
+
+	pub fn update(
+		&mut self,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		let code = &mut context.settings.challenge;
+		if input.pressed & BUTTON_LEFT != 0
+		{
+			self.cursor = (self.cursor + CODE_LENGTH - 1) % CODE_LENGTH;
+		}
+		else if input.pressed & BUTTON_RIGHT != 0
+		{
+			self.cursor = (self.cursor + 1) % CODE_LENGTH;
+		}
+		else if input.pressed & BUTTON_UP != 0
+		{
+			code.cycle(self.cursor, 1);
+		}
+		else if input.pressed & BUTTON_DOWN != 0
+		{
+			code.cycle(self.cursor, -1);
+		}
+
+		if input.pressed & BUTTON_1 != 0
+		{
+			let settings = &context.settings;
+			let code = settings.challenge;
+			let level = Level::new(
+				code.seed(),
+				code.preset(),
+				settings.movement,
+				settings.num_players,
+			);
+			let start = Transition::Replace(Scene::Level(Box::new(level)));
+			Some(Transition::Fade(Box::new(start)))
+		}
+		else if input.pressed & BUTTON_2 != 0
+		{
+			Some(Transition::Pop)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.cursor as u8);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let cursor = reader.u8()? as usize;
+		if cursor >= CODE_LENGTH
+		{
+			return None;
+		}
+		Some(Self { cursor })
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, "CHALLENGE", 10);
+		platform.text("SHARE A CODE TO", 20, 30);
+		platform.text("DODGE THE SAME", 24, 40);
+		platform.text("BALLS AS FRIENDS", 16, 50);
+
+		let code = context.settings.challenge;
+		let text = code.to_text();
+		let x = 80 - 8 * (CODE_LENGTH as i32);
+		for (i, character) in text.char_indices()
+		{
+			let cx = x + 16 * (i as i32);
+			let color = if i == self.cursor { 2 } else { 4 };
+			platform.set_draw_colors(color);
+			platform.text(&character.to_string(), cx, 76);
+			if i == self.cursor
+			{
+				platform.text("+", cx, 66);
+				platform.text("-", cx, 86);
+			}
+		}
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, code.preset().name(), 102);
+		platform.text("PRESS X TO START", 16, 130);
+		platform.text("PRESS Z TO GO BACK", 8, 140);
+	}
+}
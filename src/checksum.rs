@@ -0,0 +1,86 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! WASM-4 netplay only works if every player's cart stays in lockstep. A
+//! checksum of the level state pins down the frame where two copies of a
+//! run start to drift apart.
+
+use crate::fixed::{Fixed, Vector};
+
+const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a, which is tiny and gives the same result on every platform.
+pub struct Checksum
+{
+	hash: u64,
+}
+
+impl Checksum
+{
+	pub const fn new() -> Self
+	{
+		Self { hash: OFFSET_BASIS }
+	}
+
+	pub fn bytes(&mut self, bytes: &[u8])
+	{
+		for &byte in bytes
+		{
+			self.hash ^= byte as u64;
+			self.hash = self.hash.wrapping_mul(PRIME);
+		}
+	}
+
+	pub fn i32(&mut self, value: i32)
+	{
+		self.bytes(&value.to_le_bytes());
+	}
+
+	pub fn u64(&mut self, value: u64)
+	{
+		self.bytes(&value.to_le_bytes());
+	}
+
+	pub fn bool(&mut self, value: bool)
+	{
+		self.bytes(&[value as u8]);
+	}
+
+	pub fn fixed(&mut self, value: Fixed)
+	{
+		self.i32(value.to_bits());
+	}
+
+	pub fn vector(&mut self, value: Vector)
+	{
+		self.fixed(value.x);
+		self.fixed(value.y);
+	}
+
+	pub fn finish(&self) -> u64
+	{
+		self.hash
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_known_values()
+	{
+		assert_eq!(Checksum::new().finish(), OFFSET_BASIS);
+		let mut checksum = Checksum::new();
+		checksum.bytes(b"a");
+		assert_eq!(checksum.finish(), 0xaf63dc4c8601ec8c);
+		let mut other = Checksum::new();
+		other.bool(true);
+		assert_ne!(other.finish(), Checksum::new().finish());
+	}
+}
@@ -0,0 +1,275 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::fixed::{Fixed, Vector};
+use crate::platform::Platform;
+
+/// An axis-aligned rectangle in whole pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect
+{
+	pub x: i32,
+	pub y: i32,
+	pub width: i32,
+	pub height: i32,
+}
+
+/// The shape of an entity relative to its position, which is where its
+/// shadow touches the ground.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hitbox
+{
+	Rect(Rect),
+	Circle
+	{
+		x: i32,
+		y: i32,
+		radius: i32,
+	},
+}
+
+impl Hitbox
+{
+	pub const fn rect(x: i32, y: i32, width: i32, height: i32) -> Self
+	{
+		Hitbox::Rect(Rect {
+			x,
+			y,
+			width,
+			height,
+		})
+	}
+
+	pub const fn circle(x: i32, y: i32, radius: i32) -> Self
+	{
+		Hitbox::Circle { x, y, radius }
+	}
+
+	/// Shaves pixels off each side. Circles only shrink horizontally.
+	pub fn shrunk(self, horizontal: i32, vertical: i32) -> Self
+	{
+		match self
+		{
+			Hitbox::Rect(rect) => Hitbox::rect(
+				rect.x + horizontal,
+				rect.y + vertical,
+				rect.width - 2 * horizontal,
+				rect.height - 2 * vertical,
+			),
+			Hitbox::Circle { x, y, radius } =>
+			{
+				Hitbox::circle(x, y, radius - horizontal)
+			}
+		}
+	}
+
+	/// Places the hitbox in the world.
+	pub fn at(self, position: Vector) -> Collider
+	{
+		match self
+		{
+			Hitbox::Rect(rect) =>
+			{
+				let min = position + Vector::from_ints(rect.x, rect.y);
+				let size = Vector::from_ints(rect.width, rect.height);
+				Collider::Box {
+					min,
+					max: min + size,
+				}
+			}
+			Hitbox::Circle { x, y, radius } => Collider::Circle {
+				center: position + Vector::from_ints(x, y),
+				radius: Fixed::from_int(radius),
+			},
+		}
+	}
+}
+
+/// A hitbox that has been placed in the world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Collider
+{
+	Box
+	{
+		min: Vector, max: Vector
+	},
+	Circle
+	{
+		center: Vector, radius: Fixed
+	},
+}
+
+impl Collider
+{
+	/// Whether the two shapes overlap. Shapes that merely touch do not.
+	pub fn overlaps(&self, other: &Collider) -> bool
+	{
+		match (*self, *other)
+		{
+			(Collider::Box { min, max }, Collider::Box { min: a, max: b }) =>
+			{
+				min.x < b.x && a.x < max.x && min.y < b.y && a.y < max.y
+			}
+			(
+				Collider::Circle { center, radius },
+				Collider::Circle {
+					center: other_center,
+					radius: other_radius,
+				},
+			) =>
+			{
+				let distance = (center - other_center).length();
+				distance < radius + other_radius
+			}
+			(
+				Collider::Box { min, max },
+				Collider::Circle { center, radius },
+			)
+			| (
+				Collider::Circle { center, radius },
+				Collider::Box { min, max },
+			) =>
+			{
+				let closest = Vector::new(
+					center.x.clamp(min.x, max.x),
+					center.y.clamp(min.y, max.y),
+				);
+				(center - closest).length() < radius
+			}
+		}
+	}
+
+	/// Whether the shape lies entirely within the area.
+	pub fn is_inside(&self, area: Rect) -> bool
+	{
+		self.offset_into(area) == Vector::default()
+	}
+
+	/// How far the shape has to move to lie within the area, assuming it
+	/// fits at all.
+	pub fn offset_into(&self, area: Rect) -> Vector
+	{
+		let (min, max) = match *self
+		{
+			Collider::Box { min, max } => (min, max),
+			Collider::Circle { center, radius } => (
+				center - Vector::new(radius, radius),
+				center + Vector::new(radius, radius),
+			),
+		};
+		let area_min = Vector::from_ints(area.x, area.y);
+		let area_max = area_min + Vector::from_ints(area.width, area.height);
+		let offset = |min: Fixed, max: Fixed, area_min: Fixed, area_max| {
+			if min < area_min
+			{
+				area_min - min
+			}
+			else if max > area_max
+			{
+				area_max - max
+			}
+			else
+			{
+				Fixed::ZERO
+			}
+		};
+		Vector::new(
+			offset(min.x, max.x, area_min.x, area_max.x),
+			offset(min.y, max.y, area_min.y, area_max.y),
+		)
+	}
+
+	/// Outlines the shape, for debugging.
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		platform.set_draw_colors(0x20);
+		match *self
+		{
+			Collider::Box { min, max } =>
+			{
+				let (x, y) = min.to_ints();
+				let (width, height) = (max - min).to_ints();
+				platform.rect(x, y, width as u32, height as u32);
+			}
+			Collider::Circle { center, radius } =>
+			{
+				let (x, y) = (center - Vector::new(radius, radius)).to_ints();
+				let diameter = (radius * 2).to_int() as u32;
+				platform.oval(x, y, diameter, diameter);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_boxes()
+	{
+		let hitbox = Hitbox::rect(-4, -1, 8, 3);
+		let a = hitbox.at(Vector::from_ints(50, 50));
+		assert!(a.overlaps(&hitbox.at(Vector::from_ints(57, 52))));
+		assert!(!a.overlaps(&hitbox.at(Vector::from_ints(58, 52))));
+		assert!(!a.overlaps(&hitbox.at(Vector::from_ints(57, 53))));
+		let shrunk = hitbox.shrunk(2, 1).at(Vector::from_ints(50, 50));
+		assert!(shrunk.overlaps(&hitbox.at(Vector::from_ints(55, 51))));
+		assert!(!shrunk.overlaps(&hitbox.at(Vector::from_ints(56, 51))));
+		assert!(!shrunk.overlaps(&hitbox.at(Vector::from_ints(55, 52))));
+	}
+
+	#[test]
+	fn test_circles()
+	{
+		let circle = Hitbox::circle(0, 0, 3);
+		let a = circle.at(Vector::from_ints(50, 50));
+		assert!(a.overlaps(&circle.at(Vector::from_ints(54, 54))));
+		assert!(!a.overlaps(&circle.at(Vector::from_ints(55, 55))));
+		let square = Hitbox::rect(0, 0, 4, 4);
+		assert!(a.overlaps(&square.at(Vector::from_ints(52, 52))));
+		assert!(a.overlaps(&square.at(Vector::from_ints(46, 46))));
+		assert!(!square.at(Vector::from_ints(43, 43)).overlaps(&a));
+	}
+
+	#[test]
+	fn test_is_inside()
+	{
+		let area = Rect {
+			x: 10,
+			y: 30,
+			width: 140,
+			height: 120,
+		};
+		let hitbox = Hitbox::rect(-5, -3, 10, 6);
+		assert!(hitbox.at(Vector::from_ints(15, 33)).is_inside(area));
+		assert!(hitbox.at(Vector::from_ints(145, 147)).is_inside(area));
+		assert!(!hitbox.at(Vector::from_ints(14, 80)).is_inside(area));
+		assert!(!hitbox.at(Vector::from_ints(80, 148)).is_inside(area));
+		let circle = Hitbox::circle(0, 0, 3);
+		assert!(circle.at(Vector::from_ints(13, 33)).is_inside(area));
+		assert!(!circle.at(Vector::from_ints(12, 33)).is_inside(area));
+	}
+
+	#[test]
+	fn test_offset_into()
+	{
+		let area = Rect {
+			x: 10,
+			y: 30,
+			width: 50,
+			height: 120,
+		};
+		let hitbox = Hitbox::rect(-5, -3, 10, 6);
+		let offset = hitbox.at(Vector::from_ints(12, 80)).offset_into(area);
+		assert_eq!(offset, Vector::from_ints(3, 0));
+		let offset = hitbox.at(Vector::from_ints(70, 149)).offset_into(area);
+		assert_eq!(offset, Vector::from_ints(-15, -2));
+		let offset = hitbox.at(Vector::from_ints(30, 80)).offset_into(area);
+		assert_eq!(offset, Vector::default());
+	}
+}
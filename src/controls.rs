@@ -0,0 +1,71 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+/// How the little guy responds to the d-pad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement
+{
+	/// Moves at full speed as soon as a direction is held.
+	Instant,
+	/// Speeds up and slows down gradually.
+	Momentum,
+}
+
+impl Movement
+{
+	pub const ALL: [Movement; 2] = [Movement::Instant, Movement::Momentum];
+
+	pub fn index(self) -> usize
+	{
+		self as usize
+	}
+
+	pub fn from_index(index: usize) -> Option<Self>
+	{
+		Self::ALL.get(index).copied()
+	}
+
+	pub fn next(self) -> Self
+	{
+		Self::ALL[(self.index() + 1) % Self::ALL.len()]
+	}
+
+	pub fn name(self) -> &'static str
+	{
+		match self
+		{
+			Movement::Instant => "INSTANT",
+			Movement::Momentum => "MOMENTUM",
+		}
+	}
+}
+
+/// WASM-4 has four gamepads, which netplay also maps remote players to.
+pub const MAX_PLAYERS: usize = 4;
+
+/// The gamepads on a single frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Input
+{
+	/// The first gamepad, which controls the menus.
+	pub gamepad: u8,
+	/// The buttons of the first gamepad that went down this frame.
+	pub pressed: u8,
+	/// Every gamepad, including the first.
+	pub gamepads: [u8; MAX_PLAYERS],
+}
+
+impl Input
+{
+	pub fn new(gamepad: u8, previous_gamepad: u8) -> Self
+	{
+		Self {
+			gamepad,
+			pressed: gamepad & !previous_gamepad,
+			gamepads: [gamepad, 0, 0, 0],
+		}
+	}
+}
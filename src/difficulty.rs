@@ -0,0 +1,380 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::fixed::Fixed;
+
+pub const NUM_PRESETS: usize = 4;
+/// The most lives that can be chosen in the options.
+pub const MAX_LIVES: i32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset
+{
+	Easy,
+	Normal,
+	Hard,
+	Insane,
+}
+
+impl Preset
+{
+	pub const ALL: [Preset; NUM_PRESETS] =
+		[Preset::Easy, Preset::Normal, Preset::Hard, Preset::Insane];
+
+	pub fn index(self) -> usize
+	{
+		self as usize
+	}
+
+	pub fn from_index(index: usize) -> Option<Self>
+	{
+		Self::ALL.get(index).copied()
+	}
+
+	pub fn previous(self) -> Self
+	{
+		Self::ALL[(self.index() + NUM_PRESETS - 1) % NUM_PRESETS]
+	}
+
+	pub fn next(self) -> Self
+	{
+		Self::ALL[(self.index() + 1) % NUM_PRESETS]
+	}
+
+	pub fn name(self) -> &'static str
+	{
+		match self
+		{
+			Preset::Easy => "EASY",
+			Preset::Normal => "NORMAL",
+			Preset::Hard => "HARD",
+			Preset::Insane => "INSANE",
+		}
+	}
+
+	pub fn curve(self) -> &'static DifficultyCurve
+	{
+		match self
+		{
+			Preset::Easy => &EASY,
+			Preset::Normal => &NORMAL,
+			Preset::Hard => &HARD,
+			Preset::Insane => &INSANE,
+		}
+	}
+
+	/// How long a ball is telegraphed, given the current time between balls.
+	pub fn warning_time(self, time_between_balls: i32) -> i32
+	{
+		let (percentage, minimum) = match self
+		{
+			Preset::Easy => (100, 10),
+			Preset::Normal => (75, 5),
+			Preset::Hard => (60, 5),
+			Preset::Insane => (50, 3),
+		};
+		std::cmp::max(minimum, time_between_balls * percentage / 100)
+	}
+
+	/// How many hits the little guy can take, unless chosen otherwise in the
+	/// options. With a single life, the first hit ends the run.
+	pub const fn num_lives(self) -> i32
+	{
+		match self
+		{
+			Preset::Easy => 3,
+			Preset::Normal => 1,
+			Preset::Hard => 1,
+			Preset::Insane => 1,
+		}
+	}
+
+	/// How many times a bouncing ball ricochets off the arena walls before
+	/// it leaves the arena.
+	pub fn num_bounces(self) -> i32
+	{
+		match self
+		{
+			Preset::Easy => 1,
+			Preset::Normal => 2,
+			Preset::Hard => 2,
+			Preset::Insane => 3,
+		}
+	}
+
+	/// How many pixels are shaved off the width of hitboxes. A negative
+	/// forgiveness makes hitboxes bigger.
+	pub fn hitbox_forgiveness(self) -> i32
+	{
+		match self
+		{
+			Preset::Easy => 2,
+			Preset::Normal => 0,
+			Preset::Hard => 0,
+			Preset::Insane => -1,
+		}
+	}
+}
+
+/// How the difficulty ramps up over the course of a level. Everything is
+/// interpolated linearly between keyframes, so the speeds ramp up smoothly
+/// instead of jumping from one whole pixel per tick to the next.
+pub struct DifficultyCurve
+{
+	pub keyframes: &'static [Keyframe],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keyframe
+{
+	pub seconds: i32,
+	pub time_between_balls: i32,
+	pub min_speed: Fixed,
+	pub min_bonus: Fixed,
+	pub max_bonus: Fixed,
+	/// The percentage of balls that move diagonally.
+	pub diagonal_chance: i32,
+	/// The percentage of balls that bounce off the arena walls.
+	pub bouncing_chance: i32,
+	/// The percentage of balls that home in on the little guy.
+	pub homing_chance: i32,
+}
+
+/// The difficulty at one particular moment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Difficulty
+{
+	pub time_between_balls: i32,
+	pub min_speed: Fixed,
+	pub min_bonus: Fixed,
+	pub max_bonus: Fixed,
+	/// The percentage of balls that move diagonally.
+	pub diagonal_chance: i32,
+	/// The percentage of balls that bounce off the arena walls.
+	pub bouncing_chance: i32,
+	/// The percentage of balls that home in on the little guy.
+	pub homing_chance: i32,
+}
+
+impl DifficultyCurve
+{
+	pub fn sample(&self, ticks: i32) -> Difficulty
+	{
+		let next = self
+			.keyframes
+			.iter()
+			.position(|keyframe| keyframe.seconds * 60 > ticks)
+			.unwrap_or(self.keyframes.len());
+		let current = &self.keyframes[next.saturating_sub(1)];
+		let upcoming = match self.keyframes.get(next)
+		{
+			Some(upcoming) if next > 0 => upcoming,
+			_ => current,
+		};
+		let start = current.seconds * 60;
+		let duration = std::cmp::max(1, upcoming.seconds * 60 - start);
+		let progress = std::cmp::min(ticks - start, duration);
+		let interpolate =
+			|from: i32, to: i32| from + (to - from) * progress / duration;
+		let interpolate_speed = |from: Fixed, to: Fixed| {
+			from + (to - from) * Fixed::from_ratio(progress, duration)
+		};
+		Difficulty {
+			time_between_balls: interpolate(
+				current.time_between_balls,
+				upcoming.time_between_balls,
+			),
+			min_speed: interpolate_speed(current.min_speed, upcoming.min_speed),
+			min_bonus: interpolate_speed(current.min_bonus, upcoming.min_bonus),
+			max_bonus: interpolate_speed(current.max_bonus, upcoming.max_bonus),
+			diagonal_chance: interpolate(
+				current.diagonal_chance,
+				upcoming.diagonal_chance,
+			),
+			bouncing_chance: interpolate(
+				current.bouncing_chance,
+				upcoming.bouncing_chance,
+			),
+			homing_chance: interpolate(
+				current.homing_chance,
+				upcoming.homing_chance,
+			),
+		}
+	}
+}
+
+// Each keyframe lists its time in seconds, the time between balls in ticks,
+// the minimum speed and the minimum and maximum bonus speed of horizontal
+// balls in quarter pixels per tick, and the percentages of diagonal, bouncing
+// and homing balls.
+pub const EASY: DifficultyCurve = DifficultyCurve {
+	keyframes: &[
+		keyframe(0, 110, 4, 0, 0, 0, 0, 0),
+		keyframe(20, 90, 4, 0, 0, 0, 0, 0),
+		keyframe(45, 70, 4, 0, 4, 0, 0, 0),
+		keyframe(90, 50, 4, 4, 4, 0, 0, 0),
+		keyframe(120, 35, 8, 0, 0, 10, 0, 0),
+		keyframe(150, 25, 8, 0, 4, 15, 5, 0),
+		keyframe(180, 18, 8, 4, 4, 20, 10, 0),
+		keyframe(240, 12, 12, 0, 0, 25, 10, 0),
+		keyframe(300, 8, 12, 0, 4, 30, 15, 5),
+	],
+};
+
+pub const NORMAL: DifficultyCurve = DifficultyCurve {
+	keyframes: &[
+		keyframe(0, 90, 4, 0, 0, 0, 0, 0),
+		keyframe(10, 74, 4, 0, 4, 0, 0, 0),
+		keyframe(30, 54, 4, 4, 4, 0, 0, 0),
+		keyframe(40, 43, 4, 4, 4, 0, 0, 0),
+		keyframe(60, 25, 8, 0, 0, 10, 5, 0),
+		keyframe(75, 17, 8, 0, 4, 15, 10, 0),
+		keyframe(90, 12, 8, 4, 4, 20, 10, 0),
+		keyframe(120, 8, 12, 0, 0, 25, 15, 0),
+		keyframe(150, 6, 20, 0, 0, 30, 20, 5),
+		keyframe(200, 3, 20, 0, 0, 30, 20, 10),
+		keyframe(240, 1, 20, 0, 0, 30, 20, 10),
+	],
+};
+
+pub const HARD: DifficultyCurve = DifficultyCurve {
+	keyframes: &[
+		keyframe(0, 70, 4, 0, 4, 0, 0, 0),
+		keyframe(15, 54, 4, 4, 4, 0, 0, 0),
+		keyframe(30, 40, 8, 0, 0, 10, 5, 0),
+		keyframe(45, 25, 8, 0, 4, 15, 10, 0),
+		keyframe(60, 15, 8, 4, 4, 20, 15, 0),
+		keyframe(90, 10, 12, 0, 0, 25, 15, 0),
+		keyframe(120, 7, 16, 0, 0, 30, 20, 5),
+		keyframe(150, 4, 20, 0, 0, 35, 25, 10),
+		keyframe(180, 1, 20, 0, 0, 40, 30, 10),
+	],
+};
+
+pub const INSANE: DifficultyCurve = DifficultyCurve {
+	keyframes: &[
+		keyframe(0, 45, 8, 0, 0, 10, 5, 0),
+		keyframe(15, 30, 8, 0, 4, 15, 10, 0),
+		keyframe(30, 20, 8, 4, 4, 20, 15, 0),
+		keyframe(45, 12, 12, 0, 0, 25, 15, 0),
+		keyframe(60, 8, 12, 0, 4, 30, 20, 0),
+		keyframe(90, 5, 16, 0, 0, 35, 25, 5),
+		keyframe(120, 3, 20, 0, 0, 40, 30, 10),
+		keyframe(150, 1, 24, 0, 0, 40, 35, 15),
+	],
+};
+
+#[allow(clippy::too_many_arguments)]
+const fn keyframe(
+	seconds: i32,
+	time_between_balls: i32,
+	min_speed: i32,
+	min_bonus: i32,
+	max_bonus: i32,
+	diagonal_chance: i32,
+	bouncing_chance: i32,
+	homing_chance: i32,
+) -> Keyframe
+{
+	Keyframe {
+		seconds,
+		time_between_balls,
+		min_speed: Fixed::from_ratio(min_speed, 4),
+		min_bonus: Fixed::from_ratio(min_bonus, 4),
+		max_bonus: Fixed::from_ratio(max_bonus, 4),
+		diagonal_chance,
+		bouncing_chance,
+		homing_chance,
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn assert_monotonic(curve: &DifficultyCurve)
+	{
+		assert!(!curve.keyframes.is_empty());
+		assert_eq!(curve.keyframes[0].seconds, 0);
+		for pair in curve.keyframes.windows(2)
+		{
+			assert!(pair[0].seconds < pair[1].seconds);
+		}
+
+		let mut previous = curve.sample(0);
+		for ticks in 0..(300 * 60)
+		{
+			let difficulty = curve.sample(ticks);
+			assert!(difficulty.time_between_balls >= 1);
+			assert!(difficulty.min_speed >= Fixed::from_int(1));
+			assert!(difficulty.min_bonus <= difficulty.max_bonus);
+			assert!((0..=100).contains(&difficulty.diagonal_chance));
+			assert!(difficulty.diagonal_chance >= previous.diagonal_chance);
+			assert!((0..=100).contains(&difficulty.bouncing_chance));
+			assert!(difficulty.bouncing_chance >= previous.bouncing_chance);
+			assert!((0..=100).contains(&difficulty.homing_chance));
+			assert!(difficulty.homing_chance >= previous.homing_chance);
+			assert!(
+				difficulty.time_between_balls <= previous.time_between_balls
+			);
+			assert!(
+				difficulty.min_speed + difficulty.min_bonus
+					>= previous.min_speed + previous.min_bonus
+			);
+			assert!(
+				difficulty.min_speed + difficulty.max_bonus
+					>= previous.min_speed + previous.max_bonus
+			);
+			previous = difficulty;
+		}
+	}
+
+	#[test]
+	fn test_presets_are_monotonic()
+	{
+		for preset in Preset::ALL
+		{
+			assert_monotonic(preset.curve());
+		}
+	}
+
+	#[test]
+	fn test_presets_are_ordered()
+	{
+		for pair in Preset::ALL.windows(2)
+		{
+			let (easier, harder) = (pair[0].curve(), pair[1].curve());
+			for seconds in 0..300
+			{
+				let a = easier.sample(seconds * 60);
+				let b = harder.sample(seconds * 60);
+				assert!(a.time_between_balls >= b.time_between_balls);
+				assert!(a.min_speed + a.max_bonus <= b.min_speed + b.max_bonus);
+				assert!(a.diagonal_chance <= b.diagonal_chance);
+				assert!(a.bouncing_chance <= b.bouncing_chance);
+				assert!(a.homing_chance <= b.homing_chance);
+			}
+		}
+	}
+
+	#[test]
+	fn test_sample_interpolates()
+	{
+		assert_eq!(NORMAL.sample(0).time_between_balls, 90);
+		assert_eq!(NORMAL.sample(5 * 60).time_between_balls, 82);
+		assert_eq!(NORMAL.sample(10 * 60).time_between_balls, 74);
+		assert_eq!(NORMAL.sample(5 * 60).max_bonus, Fixed::from_ratio(1, 2));
+		assert_eq!(NORMAL.sample(10 * 60).max_bonus, Fixed::from_int(1));
+		assert_eq!(NORMAL.sample(50 * 60).min_speed, Fixed::from_ratio(3, 2));
+		assert_eq!(NORMAL.sample(1000 * 60).time_between_balls, 1);
+		assert_eq!(NORMAL.sample(1000 * 60).min_speed, Fixed::from_int(5));
+		assert_eq!(NORMAL.sample(40 * 60).diagonal_chance, 0);
+		assert_eq!(NORMAL.sample(50 * 60).diagonal_chance, 5);
+		assert_eq!(NORMAL.sample(50 * 60).bouncing_chance, 2);
+		assert_eq!(NORMAL.sample(120 * 60).homing_chance, 0);
+	}
+}
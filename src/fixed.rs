@@ -0,0 +1,271 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+const FRACTIONAL_BITS: u32 = 8;
+
+/// A fixed-point number with 8 fractional bits, so positions and speeds can
+/// be fractions of a pixel. Unlike floats, the arithmetic is exactly the same
+/// on every platform, which keeps seeded runs reproducible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(i32);
+
+impl Fixed
+{
+	pub const ZERO: Fixed = Fixed(0);
+
+	pub const fn from_int(value: i32) -> Self
+	{
+		Self(value << FRACTIONAL_BITS)
+	}
+
+	pub const fn from_ratio(numerator: i32, denominator: i32) -> Self
+	{
+		Self((numerator << FRACTIONAL_BITS) / denominator)
+	}
+
+	/// The raw representation, in 256ths.
+	pub const fn to_bits(self) -> i32
+	{
+		self.0
+	}
+
+	pub const fn from_bits(bits: i32) -> Self
+	{
+		Self(bits)
+	}
+
+	/// Rounds towards negative infinity.
+	pub const fn to_int(self) -> i32
+	{
+		self.0 >> FRACTIONAL_BITS
+	}
+
+	pub fn abs(self) -> Self
+	{
+		Self(self.0.abs())
+	}
+
+	pub fn clamp_abs(self, limit: Fixed) -> Self
+	{
+		self.clamp(-limit, limit)
+	}
+
+	pub fn sqrt(self) -> Self
+	{
+		let squared = (std::cmp::max(0, self.0) as u64) << FRACTIONAL_BITS;
+		Self(squared.isqrt() as i32)
+	}
+}
+
+impl Add for Fixed
+{
+	type Output = Fixed;
+
+	fn add(self, other: Fixed) -> Fixed
+	{
+		Fixed(self.0 + other.0)
+	}
+}
+
+impl AddAssign for Fixed
+{
+	fn add_assign(&mut self, other: Fixed)
+	{
+		self.0 += other.0;
+	}
+}
+
+impl Sub for Fixed
+{
+	type Output = Fixed;
+
+	fn sub(self, other: Fixed) -> Fixed
+	{
+		Fixed(self.0 - other.0)
+	}
+}
+
+impl SubAssign for Fixed
+{
+	fn sub_assign(&mut self, other: Fixed)
+	{
+		self.0 -= other.0;
+	}
+}
+
+impl Neg for Fixed
+{
+	type Output = Fixed;
+
+	fn neg(self) -> Fixed
+	{
+		Fixed(-self.0)
+	}
+}
+
+impl Mul for Fixed
+{
+	type Output = Fixed;
+
+	fn mul(self, other: Fixed) -> Fixed
+	{
+		Fixed(((self.0 as i64 * other.0 as i64) >> FRACTIONAL_BITS) as i32)
+	}
+}
+
+impl Mul<i32> for Fixed
+{
+	type Output = Fixed;
+
+	fn mul(self, other: i32) -> Fixed
+	{
+		Fixed(self.0 * other)
+	}
+}
+
+impl Div for Fixed
+{
+	type Output = Fixed;
+
+	fn div(self, other: Fixed) -> Fixed
+	{
+		Fixed((((self.0 as i64) << FRACTIONAL_BITS) / other.0 as i64) as i32)
+	}
+}
+
+/// A position, velocity or acceleration in fixed-point pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Vector
+{
+	pub x: Fixed,
+	pub y: Fixed,
+}
+
+impl Vector
+{
+	pub const fn new(x: Fixed, y: Fixed) -> Self
+	{
+		Self { x, y }
+	}
+
+	pub const fn from_ints(x: i32, y: i32) -> Self
+	{
+		Self::new(Fixed::from_int(x), Fixed::from_int(y))
+	}
+
+	pub const fn to_ints(self) -> (i32, i32)
+	{
+		(self.x.to_int(), self.y.to_int())
+	}
+
+	pub fn length(self) -> Fixed
+	{
+		(self.x * self.x + self.y * self.y).sqrt()
+	}
+
+	/// Scales the vector to the given length, unless it has no direction.
+	pub fn with_length(self, length: Fixed) -> Self
+	{
+		let current = self.length();
+		if current == Fixed::ZERO
+		{
+			return self;
+		}
+		Self::new(self.x * length / current, self.y * length / current)
+	}
+}
+
+impl Add for Vector
+{
+	type Output = Vector;
+
+	fn add(self, other: Vector) -> Vector
+	{
+		Vector::new(self.x + other.x, self.y + other.y)
+	}
+}
+
+impl AddAssign for Vector
+{
+	fn add_assign(&mut self, other: Vector)
+	{
+		self.x += other.x;
+		self.y += other.y;
+	}
+}
+
+impl Sub for Vector
+{
+	type Output = Vector;
+
+	fn sub(self, other: Vector) -> Vector
+	{
+		Vector::new(self.x - other.x, self.y - other.y)
+	}
+}
+
+impl Mul<Fixed> for Vector
+{
+	type Output = Vector;
+
+	fn mul(self, other: Fixed) -> Vector
+	{
+		Vector::new(self.x * other, self.y * other)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_arithmetic()
+	{
+		let a = Fixed::from_ratio(5, 4);
+		let b = Fixed::from_int(3);
+		assert_eq!((a + b).to_int(), 4);
+		assert_eq!(a * 4, Fixed::from_int(5));
+		assert_eq!(a * b, Fixed::from_ratio(15, 4));
+		assert_eq!(b / a, Fixed::from_ratio(12, 5));
+		assert_eq!((-a).to_int(), -2);
+		assert_eq!(Fixed::from_int(16).sqrt(), Fixed::from_int(4));
+		assert_eq!(Fixed::from_ratio(9, 4).sqrt(), Fixed::from_ratio(3, 2));
+		assert_eq!(b.clamp_abs(a), a);
+		assert_eq!((-b).clamp_abs(a), -a);
+	}
+
+	#[test]
+	fn test_vectors()
+	{
+		let a = Vector::from_ints(3, -4);
+		assert_eq!(a.length(), Fixed::from_int(5));
+		assert_eq!(
+			a.with_length(Fixed::from_int(10)),
+			Vector::from_ints(6, -8)
+		);
+		assert_eq!(
+			Vector::default().with_length(Fixed::from_int(1)),
+			Vector::default()
+		);
+		assert_eq!(a - a, Vector::default());
+		assert_eq!((a + a).to_ints(), (6, -8));
+		assert_eq!(
+			a * Fixed::from_ratio(1, 2),
+			Vector::new(Fixed::from_ratio(3, 2), Fixed::from_int(-2),)
+		);
+		let position = Vector::from_ints(10, 10);
+		let speed = Fixed::from_ratio(5, 4);
+		let mut moved = position;
+		for _ in 0..4
+		{
+			moved += Vector::new(speed, Fixed::ZERO);
+		}
+		assert_eq!(moved.to_ints(), (15, 10));
+	}
+}
@@ -0,0 +1,298 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::Input;
+use crate::difficulty::Preset;
+use crate::highscores::NUM_HIGH_SCORES;
+use crate::level::{Level, Trajectory, NUM_TRAJECTORIES};
+use crate::menu::{self, List};
+use crate::palette;
+use crate::platform::Platform;
+use crate::replay::Recording;
+use crate::save::{Reader, Writer};
+use crate::seed_code::SeedCode;
+use crate::title::Title;
+use crate::wasm4::*;
+use crate::{Context, Scene, Transition};
+
+/// What is left of a level once the run is over.
+pub struct Summary
+{
+	pub preset: Preset,
+	pub score: i32,
+	pub ticks: i32,
+	pub high_score_rank: Option<usize>,
+	/// Balls that left the arena without touching the little guy.
+	pub balls_dodged: [i32; NUM_TRAJECTORIES],
+	/// How close the nearest of those balls came, in pixels.
+	pub closest_call: Option<i32>,
+	pub recording: Recording,
+	/// Where the random number generator left off, to seed a new run.
+	pub next_seed: u64,
+	pub is_practice: bool,
+}
+
+impl Summary
+{
+	fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.preset.index() as u8);
+		writer.varint(self.score);
+		writer.varint(self.ticks);
+		writer
+			.option(self.high_score_rank, |writer, rank| writer.u8(rank as u8));
+		for count in self.balls_dodged
+		{
+			writer.varint(count);
+		}
+		writer.option(self.closest_call, Writer::varint);
+		self.recording.write(writer);
+		writer.u64(self.next_seed);
+		writer.bool(self.is_practice);
+	}
+
+	fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let preset = Preset::from_index(reader.u8()? as usize)?;
+		let score = reader.varint()?;
+		let ticks = reader.varint()?;
+		let high_score_rank =
+			reader.option(|reader| reader.u8())?.map(usize::from);
+		if high_score_rank.is_some_and(|rank| rank >= NUM_HIGH_SCORES)
+		{
+			return None;
+		}
+		let mut balls_dodged = [0; NUM_TRAJECTORIES];
+		for count in &mut balls_dodged
+		{
+			*count = reader.varint()?;
+		}
+		Some(Self {
+			preset,
+			score,
+			ticks,
+			high_score_rank,
+			balls_dodged,
+			closest_call: reader.option(Reader::varint)?,
+			recording: Recording::read(reader)?,
+			next_seed: reader.u64()?,
+			is_practice: reader.bool()?,
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Choice
+{
+	Retry,
+	NewSeed,
+	Watch,
+	Save,
+	Quit,
+}
+
+impl Choice
+{
+	const ALL: [Choice; 5] = [
+		Choice::Retry,
+		Choice::NewSeed,
+		Choice::Watch,
+		Choice::Save,
+		Choice::Quit,
+	];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SaveStatus
+{
+	Unsaved,
+	Saved,
+	TooLong,
+	/// The run was suspended and its input was left out to fit the disk.
+	NotRecorded,
+}
+
+impl SaveStatus
+{
+	const ALL: [SaveStatus; 4] = [
+		SaveStatus::Unsaved,
+		SaveStatus::Saved,
+		SaveStatus::TooLong,
+		SaveStatus::NotRecorded,
+	];
+}
+
+pub struct GameOver
+{
+	summary: Summary,
+	list: List,
+	save_status: SaveStatus,
+}
+
+impl GameOver
+{
+	pub fn new(summary: Summary) -> Self
+	{
+		let save_status = if summary.recording.is_complete()
+		{
+			SaveStatus::Unsaved
+		}
+		else
+		{
+			SaveStatus::NotRecorded
+		};
+		Self {
+			summary,
+			list: List::new(Choice::ALL.len()),
+			save_status,
+		}
+	}
+
+	pub fn update(
+		&mut self,
+		platform: &mut dyn Platform,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		let choice = Choice::ALL[self.list.update(input.pressed)?];
+		let summary = &self.summary;
+		let Recording {
+			seed,
+			preset,
+			movement,
+			num_players,
+			num_lives,
+			..
+		} = summary.recording;
+		let level = match choice
+		{
+			Choice::Retry => Level::new(seed, preset, movement, num_players)
+				.with_lives(num_lives),
+			Choice::NewSeed =>
+			{
+				Level::new(summary.next_seed, preset, movement, num_players)
+					.with_lives(num_lives)
+			}
+			Choice::Watch if self.save_status == SaveStatus::NotRecorded =>
+			{
+				platform.tone(200, 10, 50, TONE_PULSE1);
+				return None;
+			}
+			Choice::Watch => Level::replay(&summary.recording),
+			Choice::Save =>
+			{
+				if self.save_status == SaveStatus::Unsaved
+				{
+					let save_data = &mut context.save_data;
+					if save_data.store_replay(platform, &summary.recording)
+					{
+						self.save_status = SaveStatus::Saved;
+						platform.tone(600 | (900 << 16), 10, 50, TONE_PULSE1);
+					}
+					else
+					{
+						self.save_status = SaveStatus::TooLong;
+						platform.tone(200, 10, 50, TONE_PULSE1);
+					}
+				}
+				return None;
+			}
+			Choice::Quit =>
+			{
+				let quit = Transition::Reset(Scene::Title(Title::new()));
+				return Some(Transition::Fade(Box::new(quit)));
+			}
+		};
+		let level = if summary.is_practice && choice != Choice::Watch
+		{
+			level.for_practice()
+		}
+		else
+		{
+			level
+		};
+		Some(Transition::Replace(Scene::Level(Box::new(level))))
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		self.summary.write(writer);
+		self.list.write(writer);
+		writer.u8(self.save_status as u8);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		Some(Self {
+			summary: Summary::read(reader)?,
+			list: List::read(reader, Choice::ALL.len())?,
+			save_status: *SaveStatus::ALL.get(reader.u8()? as usize)?,
+		})
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		let summary = &self.summary;
+		platform.set_draw_colors(4);
+		let seconds = summary.ticks / 60;
+		let frac = (summary.ticks / 6) % 10;
+		platform.text(&format!("TM: {:>3}.{}", seconds, frac), 5, 5);
+		platform.text(&format!("PTS: {:>3}", summary.score), 90, 5);
+
+		let num_players = summary.recording.num_players;
+		if num_players > 1
+		{
+			let mode =
+				format!("{} CO-OP {}P", summary.preset.name(), num_players);
+			menu::draw_centered(platform, &mode, 18);
+		}
+		else
+		{
+			menu::draw_centered(platform, summary.preset.name(), 18);
+		}
+		platform.text("DODGED", 10, 30);
+		for trajectory in Trajectory::ALL
+		{
+			let y = 40 + 9 * (trajectory.index() as i32);
+			let count = summary.balls_dodged[trajectory.index()];
+			platform.text(trajectory.name(), 18, y);
+			platform.text(&format!("{:>3}", count), 122, y);
+		}
+		let closest_call = match summary.closest_call
+		{
+			Some(distance) => format!("CLOSEST CALL: {}PX", distance),
+			None => "CLOSEST CALL: -".to_string(),
+		};
+		platform.text(&closest_call, 10, 78);
+		let recording = &summary.recording;
+		let seed = match SeedCode::new(recording.preset, recording.seed)
+		{
+			Some(code) => format!("CODE: {}", code.to_text()),
+			None => format!("SEED: {:X}", recording.seed),
+		};
+		platform.text(&seed, 10, 87);
+
+		if let Some(rank) = summary.high_score_rank
+		{
+			platform.set_draw_colors(2);
+			let text = format!("NEW HIGH SCORE! #{}", rank + 1);
+			menu::draw_centered(platform, &text, 97);
+		}
+
+		let save = match self.save_status
+		{
+			SaveStatus::Unsaved => "SAVE REPLAY",
+			SaveStatus::Saved => "REPLAY SAVED",
+			SaveStatus::TooLong => "REPLAY TOO LONG",
+			SaveStatus::NotRecorded => "NOT RECORDED",
+		};
+		let names = ["RETRY SEED", "NEW SEED", "WATCH REPLAY", save, "QUIT"];
+		self.list.draw(platform, &names, 28, 110);
+	}
+}
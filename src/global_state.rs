@@ -20,6 +20,7 @@ impl<T> Wrapper<T>
 		}
 	}
 
+	#[allow(clippy::mut_from_ref)]
 	pub fn get_mut(&self) -> &mut T
 	{
 		unsafe { &mut *self.inner.get() }
@@ -0,0 +1,671 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::MAX_PLAYERS;
+use crate::platform::Platform;
+use crate::wasm4::*;
+
+const FRAMEBUFFER_SIZE: usize = (SCREEN_SIZE * SCREEN_SIZE / 4) as usize;
+const DISK_SIZE: usize = 1024;
+
+/// A software stand-in for the WASM-4 console. It renders into an in-memory
+/// 2bpp framebuffer with the same layout as `FRAMEBUFFER`, takes its input
+/// from `gamepads` and keeps its disk in memory.
+pub struct Headless
+{
+	pub gamepads: [u8; MAX_PLAYERS],
+	pub palette: [u32; 4],
+	pub framebuffer: [u8; FRAMEBUFFER_SIZE],
+	pub disk: Vec<u8>,
+	pub traces: Vec<String>,
+	draw_colors: u16,
+}
+
+impl Headless
+{
+	pub fn new() -> Self
+	{
+		Self {
+			gamepads: [0; MAX_PLAYERS],
+			palette: [0xe0f8cf, 0x86c06c, 0x306850, 0x071821],
+			framebuffer: [0; FRAMEBUFFER_SIZE],
+			disk: Vec::new(),
+			traces: Vec::new(),
+			draw_colors: 0x1203,
+		}
+	}
+
+	/// Clears the framebuffer, like the console does before each update.
+	pub fn clear(&mut self)
+	{
+		self.framebuffer = [0; FRAMEBUFFER_SIZE];
+	}
+
+	pub fn pixel(&self, x: u32, y: u32) -> u8
+	{
+		let index = (y * SCREEN_SIZE + x) as usize;
+		let shift = (index & 0x3) * 2;
+		(self.framebuffer[index / 4] >> shift) & 0x3
+	}
+
+	/// The framebuffer as 24-bit RGB triplets, using the current palette.
+	pub fn to_rgb(&self) -> Vec<u8>
+	{
+		let mut rgb =
+			Vec::with_capacity((SCREEN_SIZE * SCREEN_SIZE * 3) as usize);
+		for y in 0..SCREEN_SIZE
+		{
+			for x in 0..SCREEN_SIZE
+			{
+				let color = self.palette[self.pixel(x, y) as usize];
+				rgb.push((color >> 16) as u8);
+				rgb.push((color >> 8) as u8);
+				rgb.push(color as u8);
+			}
+		}
+		rgb
+	}
+
+	pub fn to_ppm(&self) -> Vec<u8>
+	{
+		let mut ppm =
+			format!("P6\n{} {}\n255\n", SCREEN_SIZE, SCREEN_SIZE).into_bytes();
+		ppm.extend(self.to_rgb());
+		ppm
+	}
+
+	pub fn to_png(&self) -> Vec<u8>
+	{
+		// Each scanline is prefixed with filter type 0 (none).
+		let rgb = self.to_rgb();
+		let stride = (SCREEN_SIZE * 3) as usize;
+		let mut raw = Vec::with_capacity(rgb.len() + SCREEN_SIZE as usize);
+		for scanline in rgb.chunks(stride)
+		{
+			raw.push(0);
+			raw.extend_from_slice(scanline);
+		}
+
+		let mut header = Vec::new();
+		header.extend_from_slice(&SCREEN_SIZE.to_be_bytes());
+		header.extend_from_slice(&SCREEN_SIZE.to_be_bytes());
+		// Bit depth 8, color type 2 (RGB), default compression, no interlace.
+		header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+		let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+		write_png_chunk(&mut png, b"IHDR", &header);
+		write_png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+		write_png_chunk(&mut png, b"IEND", &[]);
+		png
+	}
+
+	fn draw_point(&mut self, color_index: u16, x: i32, y: i32)
+	{
+		if color_index == 0
+			|| x < 0 || y < 0
+			|| x >= SCREEN_SIZE as i32
+			|| y >= SCREEN_SIZE as i32
+		{
+			return;
+		}
+		let color = ((color_index - 1) & 0x3) as u8;
+		let index = (y as usize) * (SCREEN_SIZE as usize) + (x as usize);
+		let shift = (index & 0x3) * 2;
+		let byte = &mut self.framebuffer[index / 4];
+		*byte = (color << shift) | (*byte & !(0x3 << shift));
+	}
+
+	fn draw_color(&self, n: u16) -> u16
+	{
+		(self.draw_colors >> (4 * n)) & 0xf
+	}
+}
+
+impl Default for Headless
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+impl Platform for Headless
+{
+	fn gamepad(&self, player: usize) -> u8
+	{
+		self.gamepads[player]
+	}
+
+	fn set_palette(&mut self, palette: [u32; 4])
+	{
+		self.palette = palette;
+	}
+
+	fn set_draw_colors(&mut self, draw_colors: u16)
+	{
+		self.draw_colors = draw_colors;
+	}
+
+	fn blit(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		flags: u32,
+	)
+	{
+		let is_2bpp = flags & BLIT_2BPP != 0;
+		let rotate = flags & BLIT_ROTATE != 0;
+		// Rotation is counter-clockwise, which flips the other way.
+		let flip_x = (flags & BLIT_FLIP_X != 0) != rotate;
+		let flip_y = flags & BLIT_FLIP_Y != 0;
+		let width = width as i32;
+		let height = height as i32;
+		for row in 0..height
+		{
+			for col in 0..width
+			{
+				let (dx, dy) = if rotate { (row, col) } else { (col, row) };
+				let px = if flip_x { width - col - 1 } else { col };
+				let py = if flip_y { height - row - 1 } else { row };
+				let bit_index = (py * width + px) as usize;
+				let color = if is_2bpp
+				{
+					let shift = 6 - (bit_index & 0x3) * 2;
+					(sprite[bit_index / 4] >> shift) & 0x3
+				}
+				else
+				{
+					let shift = 7 - (bit_index & 0x7);
+					(sprite[bit_index / 8] >> shift) & 0x1
+				};
+				let color_index = self.draw_color(color as u16);
+				self.draw_point(color_index, x + dx, y + dy);
+			}
+		}
+	}
+
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32)
+	{
+		let color_index = self.draw_color(0);
+		let dx = (x2 - x1).abs();
+		let dy = -(y2 - y1).abs();
+		let step_x = if x1 < x2 { 1 } else { -1 };
+		let step_y = if y1 < y2 { 1 } else { -1 };
+		let mut error = dx + dy;
+		let (mut x, mut y) = (x1, y1);
+		loop
+		{
+			self.draw_point(color_index, x, y);
+			if x == x2 && y == y2
+			{
+				break;
+			}
+			let e2 = 2 * error;
+			if e2 >= dy
+			{
+				error += dy;
+				x += step_x;
+			}
+			if e2 <= dx
+			{
+				error += dx;
+				y += step_y;
+			}
+		}
+	}
+
+	fn hline(&mut self, x: i32, y: i32, len: u32)
+	{
+		let color_index = self.draw_color(0);
+		for i in 0..(len as i32)
+		{
+			self.draw_point(color_index, x + i, y);
+		}
+	}
+
+	fn vline(&mut self, x: i32, y: i32, len: u32)
+	{
+		let color_index = self.draw_color(0);
+		for i in 0..(len as i32)
+		{
+			self.draw_point(color_index, x, y + i);
+		}
+	}
+
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		let fill = self.draw_color(0);
+		let stroke = self.draw_color(1);
+		let (w, h) = (width as i64, height as i64);
+		// A point is inside if it lies within the ellipse inscribed in the
+		// bounding box, measured from pixel centers in doubled coordinates.
+		let is_inside = |px: i64, py: i64| {
+			if px < 0 || py < 0 || px >= w || py >= h
+			{
+				return false;
+			}
+			let cx = 2 * px + 1 - w;
+			let cy = 2 * py + 1 - h;
+			cx * cx * h * h + cy * cy * w * w <= w * w * h * h
+		};
+		for py in 0..h
+		{
+			for px in 0..w
+			{
+				if !is_inside(px, py)
+				{
+					continue;
+				}
+				let is_edge = !is_inside(px - 1, py)
+					|| !is_inside(px + 1, py)
+					|| !is_inside(px, py - 1)
+					|| !is_inside(px, py + 1);
+				let color_index =
+					if is_edge && stroke != 0 { stroke } else { fill };
+				self.draw_point(color_index, x + px as i32, y + py as i32);
+			}
+		}
+	}
+
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		let fill = self.draw_color(0);
+		let stroke = self.draw_color(1);
+		let (w, h) = (width as i32, height as i32);
+		for dy in 0..h
+		{
+			for dx in 0..w
+			{
+				let is_edge = dx == 0 || dy == 0 || dx == w - 1 || dy == h - 1;
+				let color_index =
+					if is_edge && stroke != 0 { stroke } else { fill };
+				self.draw_point(color_index, x + dx, y + dy);
+			}
+		}
+	}
+
+	fn text(&mut self, text: &str, x: i32, y: i32)
+	{
+		let foreground = self.draw_color(0);
+		let background = self.draw_color(1);
+		let (mut cursor_x, mut cursor_y) = (x, y);
+		for c in text.chars()
+		{
+			if c == '\n'
+			{
+				cursor_x = x;
+				cursor_y += 8;
+				continue;
+			}
+			let glyph = glyph(c);
+			for (dy, row) in glyph.iter().enumerate()
+			{
+				for dx in 0..8
+				{
+					let is_set = (row >> (7 - dx)) & 1 != 0;
+					let color_index =
+						if is_set { foreground } else { background };
+					self.draw_point(
+						color_index,
+						cursor_x + dx,
+						cursor_y + dy as i32,
+					);
+				}
+			}
+			cursor_x += 8;
+		}
+	}
+
+	fn tone(
+		&mut self,
+		_frequency: u32,
+		_duration: u32,
+		_volume: u32,
+		_flags: u32,
+	)
+	{
+	}
+
+	fn diskr(&mut self, buffer: &mut [u8]) -> usize
+	{
+		let n = std::cmp::min(buffer.len(), self.disk.len());
+		buffer[..n].copy_from_slice(&self.disk[..n]);
+		n
+	}
+
+	fn diskw(&mut self, data: &[u8]) -> usize
+	{
+		let n = std::cmp::min(data.len(), DISK_SIZE);
+		self.disk = data[..n].to_vec();
+		n
+	}
+
+	fn trace(&mut self, text: &str)
+	{
+		self.traces.push(text.to_string());
+	}
+}
+
+fn glyph(c: char) -> &'static [u8; 8]
+{
+	let c = c.to_ascii_uppercase();
+	match c
+	{
+		' '..='_' => &FONT[(c as usize) - (' ' as usize)],
+		_ => &FONT[('?' as usize) - (' ' as usize)],
+	}
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8])
+{
+	png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = png.len();
+	png.extend_from_slice(kind);
+	png.extend_from_slice(data);
+	let crc = crc32(&png[start..]);
+	png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8>
+{
+	let mut zlib = vec![0x78, 0x01];
+	let mut blocks = data.chunks(0xffff).peekable();
+	while let Some(block) = blocks.next()
+	{
+		let is_final = blocks.peek().is_none();
+		zlib.push(is_final as u8);
+		let len = block.len() as u16;
+		zlib.extend_from_slice(&len.to_le_bytes());
+		zlib.extend_from_slice(&(!len).to_le_bytes());
+		zlib.extend_from_slice(block);
+	}
+	let (mut a, mut b) = (1u32, 0u32);
+	for &byte in data
+	{
+		a = (a + byte as u32) % 65521;
+		b = (b + a) % 65521;
+	}
+	zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+	zlib
+}
+
+fn crc32(data: &[u8]) -> u32
+{
+	let mut crc = 0xffffffffu32;
+	for &byte in data
+	{
+		crc ^= byte as u32;
+		for _ in 0..8
+		{
+			let mask = (crc & 1).wrapping_neg();
+			crc = (crc >> 1) ^ (0xedb88320 & mask);
+		}
+	}
+	!crc
+}
+
+// An approximation of the system font, for ' ' through '_'.
+#[rustfmt::skip]
+const FONT: [[u8; 8]; 64] = [
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+	[0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x10, 0x00], // '!'
+	[0x28, 0x28, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+	[0x28, 0x28, 0x7c, 0x28, 0x7c, 0x28, 0x28, 0x00], // '#'
+	[0x10, 0x3c, 0x50, 0x38, 0x14, 0x78, 0x10, 0x00], // '$'
+	[0x60, 0x64, 0x08, 0x10, 0x20, 0x4c, 0x0c, 0x00], // '%'
+	[0x30, 0x48, 0x50, 0x20, 0x54, 0x48, 0x34, 0x00], // '&'
+	[0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+	[0x08, 0x10, 0x20, 0x20, 0x20, 0x10, 0x08, 0x00], // '('
+	[0x20, 0x10, 0x08, 0x08, 0x08, 0x10, 0x20, 0x00], // ')'
+	[0x00, 0x10, 0x54, 0x38, 0x54, 0x10, 0x00, 0x00], // '*'
+	[0x00, 0x10, 0x10, 0x7c, 0x10, 0x10, 0x00, 0x00], // '+'
+	[0x00, 0x00, 0x00, 0x00, 0x18, 0x10, 0x20, 0x00], // ','
+	[0x00, 0x00, 0x00, 0x7c, 0x00, 0x00, 0x00, 0x00], // '-'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x30, 0x00], // '.'
+	[0x00, 0x04, 0x08, 0x10, 0x20, 0x40, 0x00, 0x00], // '/'
+	[0x38, 0x44, 0x4c, 0x54, 0x64, 0x44, 0x38, 0x00], // '0'
+	[0x10, 0x30, 0x10, 0x10, 0x10, 0x10, 0x38, 0x00], // '1'
+	[0x38, 0x44, 0x04, 0x08, 0x10, 0x20, 0x7c, 0x00], // '2'
+	[0x7c, 0x08, 0x10, 0x08, 0x04, 0x44, 0x38, 0x00], // '3'
+	[0x08, 0x18, 0x28, 0x48, 0x7c, 0x08, 0x08, 0x00], // '4'
+	[0x7c, 0x40, 0x78, 0x04, 0x04, 0x44, 0x38, 0x00], // '5'
+	[0x18, 0x20, 0x40, 0x78, 0x44, 0x44, 0x38, 0x00], // '6'
+	[0x7c, 0x04, 0x08, 0x10, 0x20, 0x20, 0x20, 0x00], // '7'
+	[0x38, 0x44, 0x44, 0x38, 0x44, 0x44, 0x38, 0x00], // '8'
+	[0x38, 0x44, 0x44, 0x3c, 0x04, 0x08, 0x30, 0x00], // '9'
+	[0x00, 0x30, 0x30, 0x00, 0x30, 0x30, 0x00, 0x00], // ':'
+	[0x00, 0x30, 0x30, 0x00, 0x30, 0x10, 0x20, 0x00], // ';'
+	[0x08, 0x10, 0x20, 0x40, 0x20, 0x10, 0x08, 0x00], // '<'
+	[0x00, 0x00, 0x7c, 0x00, 0x7c, 0x00, 0x00, 0x00], // '='
+	[0x20, 0x10, 0x08, 0x04, 0x08, 0x10, 0x20, 0x00], // '>'
+	[0x38, 0x44, 0x04, 0x08, 0x10, 0x00, 0x10, 0x00], // '?'
+	[0x38, 0x44, 0x04, 0x34, 0x54, 0x54, 0x38, 0x00], // '@'
+	[0x38, 0x44, 0x44, 0x7c, 0x44, 0x44, 0x44, 0x00], // 'A'
+	[0x78, 0x44, 0x44, 0x78, 0x44, 0x44, 0x78, 0x00], // 'B'
+	[0x38, 0x44, 0x40, 0x40, 0x40, 0x44, 0x38, 0x00], // 'C'
+	[0x70, 0x48, 0x44, 0x44, 0x44, 0x48, 0x70, 0x00], // 'D'
+	[0x7c, 0x40, 0x40, 0x78, 0x40, 0x40, 0x7c, 0x00], // 'E'
+	[0x7c, 0x40, 0x40, 0x78, 0x40, 0x40, 0x40, 0x00], // 'F'
+	[0x38, 0x44, 0x40, 0x5c, 0x44, 0x44, 0x3c, 0x00], // 'G'
+	[0x44, 0x44, 0x44, 0x7c, 0x44, 0x44, 0x44, 0x00], // 'H'
+	[0x38, 0x10, 0x10, 0x10, 0x10, 0x10, 0x38, 0x00], // 'I'
+	[0x1c, 0x08, 0x08, 0x08, 0x08, 0x48, 0x30, 0x00], // 'J'
+	[0x44, 0x48, 0x50, 0x60, 0x50, 0x48, 0x44, 0x00], // 'K'
+	[0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x7c, 0x00], // 'L'
+	[0x44, 0x6c, 0x54, 0x54, 0x44, 0x44, 0x44, 0x00], // 'M'
+	[0x44, 0x44, 0x64, 0x54, 0x4c, 0x44, 0x44, 0x00], // 'N'
+	[0x38, 0x44, 0x44, 0x44, 0x44, 0x44, 0x38, 0x00], // 'O'
+	[0x78, 0x44, 0x44, 0x78, 0x40, 0x40, 0x40, 0x00], // 'P'
+	[0x38, 0x44, 0x44, 0x44, 0x54, 0x48, 0x34, 0x00], // 'Q'
+	[0x78, 0x44, 0x44, 0x78, 0x50, 0x48, 0x44, 0x00], // 'R'
+	[0x3c, 0x40, 0x40, 0x38, 0x04, 0x04, 0x78, 0x00], // 'S'
+	[0x7c, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00], // 'T'
+	[0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x38, 0x00], // 'U'
+	[0x44, 0x44, 0x44, 0x44, 0x44, 0x28, 0x10, 0x00], // 'V'
+	[0x44, 0x44, 0x44, 0x54, 0x54, 0x54, 0x28, 0x00], // 'W'
+	[0x44, 0x44, 0x28, 0x10, 0x28, 0x44, 0x44, 0x00], // 'X'
+	[0x44, 0x44, 0x28, 0x10, 0x10, 0x10, 0x10, 0x00], // 'Y'
+	[0x7c, 0x04, 0x08, 0x10, 0x20, 0x40, 0x7c, 0x00], // 'Z'
+	[0x38, 0x20, 0x20, 0x20, 0x20, 0x20, 0x38, 0x00], // '['
+	[0x00, 0x40, 0x20, 0x10, 0x08, 0x04, 0x00, 0x00], // '\\'
+	[0x38, 0x08, 0x08, 0x08, 0x08, 0x08, 0x38, 0x00], // ']'
+	[0x10, 0x28, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0x00], // '_'
+];
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// The pixels of the framebuffer that are not the background, in reading
+	/// order, with their colors.
+	fn drawn(console: &Headless) -> Vec<(u32, u32, u8)>
+	{
+		let mut pixels = Vec::new();
+		for y in 0..SCREEN_SIZE
+		{
+			for x in 0..SCREEN_SIZE
+			{
+				let color = console.pixel(x, y);
+				if color != 0
+				{
+					pixels.push((x, y, color));
+				}
+			}
+		}
+		pixels
+	}
+
+	fn blit(
+		sprite: &[u8],
+		width: u32,
+		height: u32,
+		flags: u32,
+	) -> Vec<(u32, u32, u8)>
+	{
+		let mut console = Headless::new();
+		console.set_draw_colors(0x4320);
+		console.blit(sprite, 10, 20, width, height, flags);
+		drawn(&console)
+	}
+
+	#[test]
+	fn test_blit_flags()
+	{
+		// A 3 by 2 sprite with only its top left pixel set.
+		let sprite = [0b1000_0000];
+		assert_eq!(blit(&sprite, 3, 2, BLIT_1BPP), [(10, 20, 1)]);
+		assert_eq!(blit(&sprite, 3, 2, BLIT_FLIP_X), [(12, 20, 1)]);
+		assert_eq!(blit(&sprite, 3, 2, BLIT_FLIP_Y), [(10, 21, 1)]);
+		let flip_xy = BLIT_FLIP_X | BLIT_FLIP_Y;
+		assert_eq!(blit(&sprite, 3, 2, flip_xy), [(12, 21, 1)]);
+		// Rotating counter-clockwise stands the sprite up, and its top left
+		// ends up at the bottom left.
+		assert_eq!(blit(&sprite, 3, 2, BLIT_ROTATE), [(10, 22, 1)]);
+		let rotate_flip = BLIT_ROTATE | BLIT_FLIP_X;
+		assert_eq!(blit(&sprite, 3, 2, rotate_flip), [(10, 20, 1)]);
+	}
+
+	#[test]
+	fn test_blit_bit_depths()
+	{
+		// In 1bpp, set bits use the second draw color and clear bits the
+		// first, which is transparent here.
+		let sprite = [0b1010_0000];
+		let pixels = blit(&sprite, 4, 1, BLIT_1BPP);
+		assert_eq!(pixels, [(10, 20, 1), (12, 20, 1)]);
+
+		// In 2bpp, every pixel picks one of four draw colors.
+		let sprite = [0b11_10_01_00];
+		let pixels = blit(&sprite, 4, 1, BLIT_2BPP);
+		assert_eq!(pixels, [(10, 20, 3), (11, 20, 2), (12, 20, 1)]);
+		let pixels = blit(&sprite, 4, 1, BLIT_2BPP | BLIT_FLIP_X);
+		assert_eq!(pixels, [(11, 20, 1), (12, 20, 2), (13, 20, 3)]);
+	}
+
+	#[test]
+	fn test_rect_has_an_outline()
+	{
+		let mut console = Headless::new();
+		console.set_draw_colors(0x32);
+		console.rect(10, 10, 4, 3);
+		assert_eq!(console.pixel(10, 10), 2);
+		assert_eq!(console.pixel(13, 12), 2);
+		assert_eq!(console.pixel(11, 11), 1);
+		assert_eq!(console.pixel(12, 11), 1);
+		assert_eq!(console.pixel(14, 10), 0);
+		assert_eq!(drawn(&console).len(), 12);
+
+		// Without a stroke color, the outline is filled in too.
+		console.clear();
+		console.set_draw_colors(0x02);
+		console.rect(10, 10, 4, 3);
+		assert!(drawn(&console).iter().all(|&(_, _, color)| color == 1));
+		assert_eq!(drawn(&console).len(), 12);
+
+		// Whatever falls off the screen is clipped.
+		console.clear();
+		console.rect(-2, -2, 4, 4);
+		assert_eq!(
+			drawn(&console),
+			[(0, 0, 1), (1, 0, 1), (0, 1, 1), (1, 1, 1)]
+		);
+	}
+
+	#[test]
+	fn test_oval_is_inscribed_in_its_box()
+	{
+		let mut console = Headless::new();
+		console.set_draw_colors(0x32);
+		console.oval(0, 0, 5, 5);
+		assert_eq!(console.pixel(0, 0), 0);
+		assert_eq!(console.pixel(4, 4), 0);
+		assert_eq!(console.pixel(0, 2), 2);
+		assert_eq!(console.pixel(2, 4), 2);
+		assert_eq!(console.pixel(2, 2), 1);
+		assert!(drawn(&console).iter().all(|&(x, y, _)| x < 5 && y < 5));
+
+		// The same oval, mirrored, covers the same pixels.
+		let pixels = drawn(&console);
+		for &(x, y, color) in &pixels
+		{
+			assert_eq!(console.pixel(4 - x, y), color);
+			assert_eq!(console.pixel(x, 4 - y), color);
+		}
+	}
+
+	#[test]
+	fn test_text_uses_the_font()
+	{
+		let mut console = Headless::new();
+		console.set_draw_colors(0x04);
+		console.text("i\nI", 0, 0);
+		// The top row of an I is three pixels wide, and lowercase letters
+		// are drawn as uppercase.
+		for y in [0, 8]
+		{
+			let row: Vec<u8> = (0..8).map(|x| console.pixel(x, y)).collect();
+			assert_eq!(row, [0, 0, 3, 3, 3, 0, 0, 0]);
+		}
+		assert_eq!(console.pixel(8, 0), 0);
+
+		// A background color fills the rest of each glyph.
+		console.clear();
+		console.set_draw_colors(0x24);
+		console.text("I", 0, 0);
+		assert_eq!(console.pixel(0, 0), 1);
+		assert_eq!(console.pixel(2, 0), 3);
+		assert_eq!(console.pixel(7, 7), 1);
+		assert_eq!(drawn(&console).len(), 64);
+	}
+
+	#[test]
+	fn test_ppm_header()
+	{
+		let mut console = Headless::new();
+		console.set_palette([0x123456, 0, 0, 0]);
+		let ppm = console.to_ppm();
+		let header = b"P6\n160 160\n255\n";
+		assert_eq!(&ppm[..header.len()], header);
+		let pixels = &ppm[header.len()..];
+		assert_eq!(pixels.len(), (SCREEN_SIZE * SCREEN_SIZE * 3) as usize);
+		assert_eq!(&pixels[..3], [0x12, 0x34, 0x56]);
+	}
+
+	#[test]
+	fn test_png_header()
+	{
+		let console = Headless::new();
+		let png = console.to_png();
+		assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+		// The IHDR chunk comes first.
+		assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+		assert_eq!(&png[16..20], SCREEN_SIZE.to_be_bytes());
+		assert_eq!(&png[20..24], SCREEN_SIZE.to_be_bytes());
+		assert_eq!(&png[24..29], [8, 2, 0, 0, 0]);
+		assert_eq!(&png[29..33], crc32(&png[12..29]).to_be_bytes());
+		// Then a single IDAT chunk with the zlib stream.
+		let len = u32::from_be_bytes(png[33..37].try_into().unwrap());
+		assert_eq!(&png[37..41], b"IDAT");
+		assert_eq!(&png[41..43], [0x78, 0x01]);
+		let end = 41 + len as usize + 4;
+		// And the IEND chunk last.
+		assert_eq!(&png[end..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+	}
+
+	#[test]
+	fn test_checksums()
+	{
+		assert_eq!(crc32(b"123456789"), 0xcbf43926);
+		let zlib = zlib_stored(b"Wikipedia");
+		assert_eq!(&zlib[zlib.len() - 4..], 0x11e60398u32.to_be_bytes());
+	}
+}
@@ -0,0 +1,131 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+
+pub const NUM_HIGH_SCORES: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry
+{
+	pub score: i32,
+	pub ticks: i32,
+	pub seed: u64,
+	pub run_id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HighScores
+{
+	entries: Vec<Entry>,
+	num_runs: u32,
+}
+
+impl HighScores
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			entries: Vec::new(),
+			num_runs: 0,
+		}
+	}
+
+	pub fn entries(&self) -> &[Entry]
+	{
+		&self.entries
+	}
+
+	/// Adds a finished run and returns its rank if it made the table.
+	pub fn submit(&mut self, score: i32, ticks: i32, seed: u64)
+		-> Option<usize>
+	{
+		self.num_runs = self.num_runs.wrapping_add(1);
+		let entry = Entry {
+			score,
+			ticks,
+			seed,
+			run_id: self.num_runs,
+		};
+		let rank = self
+			.entries
+			.iter()
+			.position(|other| {
+				(entry.score, entry.ticks) > (other.score, other.ticks)
+			})
+			.unwrap_or(self.entries.len());
+		if rank >= NUM_HIGH_SCORES
+		{
+			return None;
+		}
+		self.entries.insert(rank, entry);
+		self.entries.truncate(NUM_HIGH_SCORES);
+		Some(rank)
+	}
+
+	pub fn draw(
+		&self,
+		platform: &mut dyn Platform,
+		y: i32,
+		highlight: Option<usize>,
+	)
+	{
+		platform.set_draw_colors(4);
+		platform.text("HIGH SCORES", 36, y);
+		for i in 0..NUM_HIGH_SCORES
+		{
+			let line = match self.entries().get(i)
+			{
+				Some(entry) => format!(
+					"{}. {:>3} {:>4}.{}",
+					i + 1,
+					entry.score,
+					entry.ticks / 60,
+					(entry.ticks / 6) % 10
+				),
+				None => format!("{}.   -      -", i + 1),
+			};
+			let color = if highlight == Some(i) { 2 } else { 4 };
+			platform.set_draw_colors(color);
+			platform.text(&line, 24, y + 15 + 10 * (i as i32));
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.u32(self.num_runs);
+		writer.u8(self.entries.len() as u8);
+		for entry in &self.entries
+		{
+			writer.i32(entry.score);
+			writer.i32(entry.ticks);
+			writer.u64(entry.seed);
+			writer.u32(entry.run_id);
+		}
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let num_runs = reader.u32()?;
+		let num_entries = reader.u8()? as usize;
+		if num_entries > NUM_HIGH_SCORES
+		{
+			return None;
+		}
+		let mut entries = Vec::with_capacity(num_entries);
+		for _ in 0..num_entries
+		{
+			entries.push(Entry {
+				score: reader.i32()?,
+				ticks: reader.i32()?,
+				seed: reader.u64()?,
+				run_id: reader.u32()?,
+			});
+		}
+		Some(Self { entries, num_runs })
+	}
+}
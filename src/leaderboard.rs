@@ -0,0 +1,74 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::Input;
+use crate::difficulty::Preset;
+use crate::menu;
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::wasm4::*;
+use crate::{Context, Transition};
+
+/// Browses the high scores of each preset.
+pub struct Leaderboard
+{
+	preset: Preset,
+}
+
+impl Leaderboard
+{
+	pub const fn new(preset: Preset) -> Self
+	{
+		Self { preset }
+	}
+
+	pub fn update(&mut self, input: Input) -> Option<Transition>
+	{
+		if input.pressed & BUTTON_LEFT != 0
+		{
+			self.preset = self.preset.previous();
+		}
+		else if input.pressed & BUTTON_RIGHT != 0
+		{
+			self.preset = self.preset.next();
+		}
+
+		if input.pressed & (BUTTON_1 | BUTTON_2) != 0
+		{
+			Some(Transition::Pop)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.preset.index() as u8);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let preset = Preset::from_index(reader.u8()? as usize)?;
+		Some(Self { preset })
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		menu::draw_carousel(platform, self.preset.name(), 10);
+
+		let high_scores = &context.save_data.high_scores[self.preset.index()];
+		high_scores.draw(platform, 40, None);
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, "PRESS Z TO GO BACK", 140);
+	}
+}
@@ -4,439 +4,2914 @@
 // License: MIT
 //
 
+use crate::checksum::Checksum;
+use crate::collision::{Collider, Hitbox, Rect};
+use crate::controls::{Input, Movement, MAX_PLAYERS};
+use crate::difficulty::{Difficulty, Preset};
+use crate::fixed::{Fixed, Vector};
+use crate::game_over::{GameOver, Summary};
 use crate::palette;
+use crate::pause::{Pause, PAUSE_COMBO, PAUSE_GRACE_TIME, PAUSE_HOLD_TIME};
+use crate::platform::Platform;
+use crate::replay::{Playback, Recording};
+use crate::save::{Reader, Writer};
+use crate::seed_code::{self, SeedCode};
 use crate::sprites;
+use crate::versus::{Scoreboard, Standings};
 use crate::wasm4::*;
-use fastrand;
+use crate::{Context, Scene, Transition};
 
 const BANNER_HEIGHT: u32 = 20;
 const PADDING_SIZE: u32 = 10;
 
+pub const ARENA: Rect = Rect {
+	x: PADDING_SIZE as i32,
+	y: (BANNER_HEIGHT + PADDING_SIZE) as i32,
+	width: (SCREEN_SIZE - 2 * PADDING_SIZE) as i32,
+	height: (SCREEN_SIZE - BANNER_HEIGHT - 2 * PADDING_SIZE) as i32,
+};
+
+/// Outline every hitbox, to help tune them.
+const SHOW_HITBOXES: bool = cfg!(feature = "debug-hitboxes");
+/// Holding Z lets the little guy leave the arena, to look around its edges.
+const CAN_WALK_THROUGH_WALLS: bool = cfg!(feature = "debug-walls");
+
 pub struct Level
 {
+	preset: Preset,
 	rng: fastrand::Rng,
-	little_guy: LittleGuy,
+	little_guys: Vec<LittleGuy>,
 	balls: Vec<Ball>,
 	score: i32,
 	ticks: i32,
 	time_until_next_ball: i32,
-	time_between_balls: i32,
+	recording: Recording,
+	playback: Option<Playback>,
+	/// Shown so that others can play the same run.
+	seed_code: Option<SeedCode>,
+	high_score_rank: Option<usize>,
+	balls_dodged: [i32; NUM_TRAJECTORIES],
+	/// How close the little guy came to a ball that missed, in pixels.
+	closest_call: Option<Fixed>,
+	/// After unpausing, the level waits until X and Z are released.
+	is_waiting_for_release: bool,
+	/// Buttons of the first gamepad that might be part of the pause combo,
+	/// and for how long they have been held back.
+	held_back: u8,
+	time_held_back: i32,
+	/// Set when the players throw balls at each other instead.
+	standings: Option<Standings>,
+	/// How long the last player standing gets to celebrate.
+	time_until_scoreboard: i32,
+	/// Practice runs can be rewound, but do not count for high scores.
+	is_practice: bool,
+	/// Snapshots of the level taken every so often, oldest first.
+	rewind_points: Vec<RewindPoint>,
+}
+
+/// The state of a practice run at some tick, apart from the recording, which
+/// only needs to be cut back to the same number of frames.
+struct RewindPoint
+{
+	state: Vec<u8>,
+	num_frames: u32,
 }
 
+/// Points for catching a ball instead of dodging it.
+const CATCH_BONUS: i32 = 5;
+/// How often a new ball is dropped into a versus round, in ticks.
+const VERSUS_DROP_TIME: i32 = 120;
+/// How long a versus round goes on after it has been decided, in ticks.
+const ROUND_END_TIME: i32 = 90;
+/// In team rounds, the strips at either end of the arena where players who
+/// have been hit wait to get back in.
+const JAIL_WIDTH: i32 = 20;
+/// How often a practice run can be rewound to, in ticks.
+const REWIND_INTERVAL: i32 = 60;
+const MAX_REWIND_POINTS: usize = 10;
+/// Each team's half of the court in team rounds, left team first.
+const HALVES: [Rect; 2] = [
+	Rect {
+		x: ARENA.x + JAIL_WIDTH,
+		y: ARENA.y,
+		width: ARENA.width / 2 - JAIL_WIDTH,
+		height: ARENA.height,
+	},
+	Rect {
+		x: ARENA.x + ARENA.width / 2,
+		y: ARENA.y,
+		width: ARENA.width / 2 - JAIL_WIDTH,
+		height: ARENA.height,
+	},
+];
+/// Each team's jail lies behind the other team's half.
+const JAILS: [Rect; 2] = [
+	Rect {
+		x: ARENA.x + ARENA.width - JAIL_WIDTH,
+		y: ARENA.y,
+		width: JAIL_WIDTH,
+		height: ARENA.height,
+	},
+	Rect {
+		x: ARENA.x,
+		y: ARENA.y,
+		width: JAIL_WIDTH,
+		height: ARENA.height,
+	},
+];
+
 impl Level
 {
-	pub fn new(rng_seed: u64) -> Self
+	pub fn new(
+		rng_seed: u64,
+		preset: Preset,
+		movement: Movement,
+		num_players: usize,
+	) -> Self
 	{
+		let little_guys = (0..num_players)
+			.map(|player| {
+				LittleGuy::new(preset.num_lives(), movement)
+					.for_player(player, num_players)
+			})
+			.collect();
 		Self {
+			preset,
 			rng: fastrand::Rng::with_seed(rng_seed),
-			little_guy: LittleGuy::new(),
+			little_guys,
 			balls: Vec::new(),
 			score: 0,
 			ticks: 0,
 			time_until_next_ball: 0,
-			time_between_balls: 90,
+			recording: Recording::new(rng_seed, preset, movement, num_players),
+			playback: None,
+			seed_code: SeedCode::new(preset, rng_seed),
+			high_score_rank: None,
+			balls_dodged: [0; NUM_TRAJECTORIES],
+			closest_call: None,
+			is_waiting_for_release: false,
+			held_back: 0,
+			time_held_back: 0,
+			standings: None,
+			time_until_scoreboard: ROUND_END_TIME,
+			is_practice: false,
+			rewind_points: Vec::new(),
 		}
 	}
 
-	pub fn update(&mut self)
+	pub fn for_practice(self) -> Self
 	{
-		self.little_guy.update();
-		for ball in &mut self.balls
+		Self {
+			is_practice: true,
+			..self
+		}
+	}
+
+	/// Gives every little guy this many lives instead of the preset's.
+	pub fn with_lives(mut self, num_lives: i32) -> Self
+	{
+		for little_guy in &mut self.little_guys
 		{
-			ball.update();
+			little_guy.lives = num_lives;
 		}
+		self.recording.num_lives = num_lives;
+		self
+	}
 
-		let num_gone = self.balls.iter().filter(|ball| ball.is_gone).count();
-		self.balls.retain(|ball| !ball.is_gone);
+	/// A round in which every player has a single life and the only balls
+	/// are the ones they throw.
+	pub fn versus(
+		rng_seed: u64,
+		movement: Movement,
+		standings: Standings,
+	) -> Self
+	{
+		let num_players = standings.num_players;
+		let little_guys = (0..num_players)
+			.map(|player| {
+				let little_guy = LittleGuy::new(1, movement).with_throwing();
+				if standings.is_teams
+				{
+					little_guy.for_team(player, num_players)
+				}
+				else
+				{
+					little_guy.for_player(player, num_players)
+				}
+			})
+			.collect();
+		Self {
+			little_guys,
+			seed_code: None,
+			standings: Some(standings),
+			..Self::new(rng_seed, Preset::Normal, movement, num_players)
+		}
+	}
+
+	pub fn replay(recording: &Recording) -> Self
+	{
+		let level = Self::new(
+			recording.seed,
+			recording.preset,
+			recording.movement,
+			recording.num_players,
+		);
+		Self {
+			playback: Some(recording.playback()),
+			..level.with_lives(recording.num_lives)
+		}
+	}
 
-		if self.little_guy.is_alive()
+	pub fn update(
+		&mut self,
+		platform: &mut dyn Platform,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		if self.is_waiting_for_release
 		{
-			for ball in &self.balls
+			if input.gamepad & PAUSE_COMBO != 0
 			{
-				if ball.detect_collision(self.little_guy.x, self.little_guy.y)
-				{
-					self.little_guy.kill();
-					tone(250, 5 | (10 << 8), 100, TONE_NOISE);
-					tone(10, 20 | (80 << 8), 100, TONE_PULSE1);
-				}
+				return None;
 			}
+			self.is_waiting_for_release = false;
+		}
+		let (gamepad, is_pausing) = self.hold_back(input);
+		if is_pausing && self.is_anyone_alive()
+		{
+			self.is_waiting_for_release = true;
+			Pause::silence(platform);
+			let pause = Pause::new(self.can_rewind());
+			return Some(Transition::Push(Scene::Pause(pause)));
+		}
 
-			self.ticks += 1;
-			self.score += num_gone as i32;
+		let was_alive = self.is_anyone_alive();
+		let gamepads = if was_alive
+		{
+			let gamepads =
+				match self.playback.as_mut().and_then(Playback::next_gamepads)
+				{
+					Some(gamepads) => gamepads,
+					None =>
+					{
+						let mut gamepads = input.gamepads;
+						gamepads[0] = gamepad;
+						gamepads
+					}
+				};
+			self.recording.push(gamepads);
+			gamepads
+		}
+		else
+		{
+			[0; MAX_PLAYERS]
+		};
+		for (little_guy, gamepad) in self.little_guys.iter_mut().zip(gamepads)
+		{
+			let gamepad = if little_guy.is_alive() { gamepad } else { 0 };
+			little_guy.update(platform, gamepad);
+		}
+		for ball in &mut self.balls
+		{
+			let target = nearest_position(&self.little_guys, ball.position);
+			ball.update(platform, target);
+		}
 
-			if self.time_until_next_ball <= 0
+		if let Some(standings) = self.standings
+		{
+			return self.referee(platform, standings);
+		}
+
+		let num_gone = self
+			.balls
+			.iter()
+			.filter(|ball| ball.is_gone && !ball.is_caught())
+			.count();
+		let num_bounces =
+			self.balls.iter().filter(|ball| ball.has_bounced).count();
+		if self.is_anyone_alive()
+		{
+			let dodged = self
+				.balls
+				.iter()
+				.filter(|ball| ball.is_gone && !ball.has_touched);
+			for ball in dodged
 			{
-				let (min_speed, min_bonus, max_bonus) = if self.ticks > 150 * 60
+				self.balls_dodged[ball.trajectory.index()] += 1;
+				if let Some(distance) = ball.closest_distance
 				{
-					(5, 0, 0)
+					self.closest_call = Some(
+						self.closest_call.map_or(distance, |d| d.min(distance)),
+					);
 				}
-				else if self.ticks > 120 * 60
+			}
+		}
+		self.balls.retain(|ball| !ball.is_gone);
+
+		if self.is_anyone_alive()
+		{
+			let forgiveness = self.preset.hitbox_forgiveness();
+			let alive = self.little_guys.iter_mut().filter(|x| x.is_alive());
+			for little_guy in alive
+			{
+				if little_guy.has_pressed_grab
 				{
-					(3, 0, 0)
+					little_guy.try_catch();
 				}
-				else if self.ticks > 90 * 60
+				let reach = little_guy.reach();
+				let catchable = self.balls.iter_mut().find(|ball| {
+					ball.is_in_flight() && ball.collider().overlaps(&reach)
+				});
+				if let (true, Some(ball)) =
+					(little_guy.is_catching(), catchable)
 				{
-					(2, 1, 1)
+					ball.catch(little_guy.position);
+					little_guy.hold_catch();
+					little_guy.play_catch_sound(platform);
+					self.score += CATCH_BONUS;
 				}
-				else if self.ticks > 75 * 60
+
+				let hitbox = little_guy.collider(forgiveness);
+				for ball in &mut self.balls
 				{
-					(2, 0, 1)
+					let distance =
+						(ball.position - little_guy.position).length();
+					ball.closest_distance = Some(
+						ball.closest_distance
+							.map_or(distance, |d| d.min(distance)),
+					);
+					if !ball.is_in_flight()
+						|| !ball.collider().overlaps(&hitbox)
+					{
+						continue;
+					}
+					ball.has_touched = true;
+					if !little_guy.is_invulnerable()
+					{
+						little_guy.hit();
+						little_guy.play_hit_sound(platform);
+					}
 				}
-				else if self.ticks > 60 * 60
+			}
+
+			self.ticks += 1;
+			self.score += (num_gone + num_bounces) as i32;
+
+			if self.time_until_next_ball <= 0
+			{
+				let Difficulty {
+					time_between_balls,
+					min_speed,
+					min_bonus,
+					max_bonus,
+					diagonal_chance,
+					bouncing_chance,
+					homing_chance,
+				} = self.preset.curve().sample(self.ticks);
+				let roll = self.rng.i32(0..100);
+				let trajectory = if roll < homing_chance
 				{
-					(2, 0, 0)
+					Trajectory::Homing
 				}
-				else if self.ticks > 30 * 60
+				else if roll < homing_chance + diagonal_chance
 				{
-					(1, 1, 1)
+					Trajectory::Diagonal
 				}
-				else if self.ticks > 10 * 60
+				else if self.rng.bool()
 				{
-					(1, 0, 1)
+					Trajectory::Horizontal
 				}
 				else
 				{
-					(1, 0, 0)
+					Trajectory::Vertical
+				};
+				let bonus_speed = match trajectory
+				{
+					Trajectory::Horizontal =>
+					{
+						let quarters = self.rng.i32(0..=4);
+						min_bonus
+							+ (max_bonus - min_bonus)
+								* Fixed::from_ratio(quarters, 4)
+					}
+					_ => Fixed::ZERO,
 				};
-				let is_horizontal = self.rng.bool();
-				let bonus_speed = if is_horizontal
+				let num_bounces = if self.rng.i32(0..100) < bouncing_chance
+					&& trajectory != Trajectory::Homing
 				{
-					self.rng.i32(min_bonus..=max_bonus)
+					self.preset.num_bounces()
 				}
 				else
 				{
 					0
 				};
-				let warning_time =
-					std::cmp::max(5, self.time_between_balls * 3 / 4);
+				let warning_time = self.preset.warning_time(time_between_balls);
 				self.balls.push(Ball::new(
-					is_horizontal,
+					trajectory,
 					min_speed,
 					bonus_speed,
+					num_bounces,
 					warning_time,
 					&mut self.rng,
 				));
 				self.time_until_next_ball =
-					std::cmp::max(1, self.time_between_balls);
-				if self.time_between_balls > 70
-				{
-					self.time_between_balls -= 2;
-				}
-				else if self.time_between_balls > 45
-				{
-					self.time_between_balls -= 1;
-				}
-				else if self.time_between_balls > 30 && self.rng.bool()
-				{
-					self.time_between_balls -= 1;
-				}
-				else if self.time_between_balls > 20
-					&& self.rng.i32(0..4) == 0
-				{
-					self.time_between_balls -= 1;
-				}
-				else if self.time_between_balls > 10
-					&& self.rng.i32(0..10) == 0
-				{
-					self.time_between_balls -= 1;
-				}
-				else if self.rng.i32(0..100) == 0
-				{
-					self.time_between_balls -= 1;
-				}
+					std::cmp::max(1, time_between_balls);
 			}
 			else if self.time_until_next_ball > 0
 			{
 				self.time_until_next_ball -= 1;
 			}
+
+			if self.is_practice && self.ticks % REWIND_INTERVAL == 0
+			{
+				if self.rewind_points.len() >= MAX_REWIND_POINTS
+				{
+					self.rewind_points.remove(0);
+				}
+				let mut writer = Writer::new();
+				self.write_state(&mut writer);
+				self.rewind_points.push(RewindPoint {
+					state: writer.into_bytes(),
+					num_frames: self.recording.num_frames(),
+				});
+			}
 		}
 		else if self.balls.is_empty()
 		{
-			let gamepad = unsafe { *GAMEPAD1 };
-			if gamepad & BUTTON_1 != 0
+			let summary = Summary {
+				preset: self.preset,
+				score: self.score,
+				ticks: self.ticks,
+				high_score_rank: self.high_score_rank,
+				balls_dodged: self.balls_dodged,
+				closest_call: self.closest_call.map(Fixed::to_int),
+				recording: self.recording.clone(),
+				next_seed: seed_code::truncate(self.rng.get_seed()),
+				is_practice: self.is_practice,
+			};
+			let game_over = Scene::GameOver(Box::new(GameOver::new(summary)));
+			return Some(Transition::Replace(game_over));
+		}
+
+		if was_alive && !self.is_anyone_alive()
+		{
+			if self.recording.is_complete()
 			{
-				self.restart();
+				platform.trace(&format!("REPLAY {}", self.recording.to_hex()));
+			}
+			// Co-op runs are not comparable to solo runs, nor are runs with
+			// more or fewer lives than the preset.
+			if self.playback.is_none()
+				&& !self.is_practice
+				&& self.recording.num_players == 1
+				&& self.recording.num_lives == self.preset.num_lives()
+			{
+				let save_data = &mut context.save_data;
+				let high_scores =
+					&mut save_data.high_scores[self.preset.index()];
+				self.high_score_rank = high_scores.submit(
+					self.score,
+					self.ticks,
+					self.recording.seed,
+				);
+				save_data.store(platform);
 			}
 		}
+
+		None
 	}
 
-	pub fn draw(&mut self)
+	pub fn draw(&self, platform: &mut dyn Platform)
 	{
-		unsafe {
-			*PALETTE = palette::SODACAP_PALETTE;
-		}
+		platform.set_palette(palette::SODACAP_PALETTE);
 
-		unsafe {
-			*DRAW_COLORS = 4;
-		}
+		platform.set_draw_colors(4);
 
 		let seconds = self.ticks / 60;
 		let frac = (self.ticks / 6) % 10;
 		let score = self.score;
-		text(format!("TM: {:>3}.{}", seconds, frac), 5, 5);
-		text(format!("PTS: {:>3}", score), 90, 5);
+		if let Some(standings) = self.standings
+		{
+			let round = standings.rounds_played + 1;
+			platform.text(&format!("ROUND {}", round), 5, 5);
+			let needed = standings.wins_needed();
+			platform.text(&format!("FIRST TO {}", needed), 75, 5);
+		}
+		else if self.recording.num_lives > 1
+		{
+			// Squeeze the HUD to make room for the lives.
+			platform.text(&format!("TM:{:>3}.{}", seconds, frac), 4, 5);
+			platform.text(&format!("PTS:{:>3}", score), 76, 5);
+			sprites::heart::draw(platform, 138, 5);
+			platform.set_draw_colors(4);
+			let lives: i32 = self.little_guys.iter().map(|x| x.lives).sum();
+			platform.text(&format!("{}", lives), 148, 5);
+		}
+		else
+		{
+			platform.text(&format!("TM: {:>3}.{}", seconds, frac), 5, 5);
+			platform.text(&format!("PTS: {:>3}", score), 90, 5);
+		}
 
-		if !self.little_guy.is_alive() && self.balls.is_empty()
+		for (i, little_guy) in self.little_guys.iter().enumerate()
 		{
-			text("PRESS X TO RESTART", 10, 140);
-			return;
+			if little_guy.is_alive()
+			{
+				little_guy.draw_dash_meter(platform, 5 + 38 * (i as i32), 14);
+			}
 		}
 
-		unsafe { *DRAW_COLORS = 0x40 }
-		rect(
-			PADDING_SIZE as i32,
-			(BANNER_HEIGHT + PADDING_SIZE) as i32,
-			SCREEN_SIZE - 2 * PADDING_SIZE,
-			SCREEN_SIZE - BANNER_HEIGHT - 2 * PADDING_SIZE,
-		);
+		platform.set_draw_colors(4);
+		if let Some(code) = self.seed_code
+		{
+			platform.text(&code.to_text(), 10, 151);
+		}
+		if self.playback.is_some() && self.is_anyone_alive()
+		{
+			platform.text("REPLAY", 102, 151);
+		}
+		else if self.is_practice
+		{
+			platform.text("PRACTICE", 94, 151);
+		}
 
-		for ball in &self.balls
+		platform.set_draw_colors(0x40);
+		platform.rect(
+			ARENA.x,
+			ARENA.y,
+			ARENA.width as u32,
+			ARENA.height as u32,
+		);
+		if self.standings.is_some_and(|x| x.is_teams)
 		{
-			if ball.y < self.little_guy.y
+			platform.set_draw_colors(4);
+			let height = ARENA.height as u32;
+			platform.vline(ARENA.x + ARENA.width / 2, ARENA.y, height);
+			for x in [JAILS[1].x + JAIL_WIDTH, JAILS[0].x]
 			{
-				ball.draw();
+				for y in (ARENA.y..ARENA.y + ARENA.height).step_by(4)
+				{
+					platform.vline(x, y, 2);
+				}
 			}
 		}
-		self.little_guy.draw();
+
+		// Whatever is further down the screen is drawn on top; little guys
+		// go first when tied.
+		enum Entity<'a>
+		{
+			LittleGuy(&'a LittleGuy),
+			Ball(&'a Ball),
+		}
+		let mut entities: Vec<(Fixed, Entity)> =
+			Vec::with_capacity(self.balls.len() + self.little_guys.len());
+		for little_guy in &self.little_guys
+		{
+			let y = little_guy.position.y;
+			entities.push((y, Entity::LittleGuy(little_guy)));
+		}
 		for ball in &self.balls
 		{
-			if ball.y >= self.little_guy.y
+			entities.push((ball.position.y, Entity::Ball(ball)));
+		}
+		entities
+			.sort_by_key(|(y, entity)| (*y, matches!(entity, Entity::Ball(_))));
+		for (_, entity) in entities
+		{
+			match entity
 			{
-				ball.draw();
+				Entity::LittleGuy(little_guy) => little_guy.draw(platform),
+				Entity::Ball(ball) => ball.draw(platform),
 			}
 		}
-	}
-
-	fn restart(&mut self)
-	{
-		self.little_guy = LittleGuy::new();
-		self.score = 0;
-		self.ticks = 0;
-		self.time_until_next_ball = 0;
-		self.time_between_balls = 90;
-	}
-}
-
-struct LittleGuy
-{
-	x: i32,
-	y: i32,
-	sprite: sprites::little_guy::Animation,
-	is_dead: bool,
-}
 
-impl LittleGuy
-{
-	pub fn new() -> Self
-	{
-		Self {
-			x: 80,
-			y: 120,
-			sprite: sprites::little_guy::Animation::new(),
-			is_dead: false,
+		if SHOW_HITBOXES
+		{
+			let forgiveness = self.preset.hitbox_forgiveness();
+			for little_guy in &self.little_guys
+			{
+				little_guy.collider(forgiveness).draw(platform);
+				little_guy.footprint().draw(platform);
+			}
+			for ball in &self.balls
+			{
+				ball.collider().draw(platform);
+			}
 		}
 	}
 
-	pub fn update(&mut self)
+	/// A hash of everything that can affect how the level plays out, which
+	/// must be the same on every machine that is fed the same input.
+	pub fn checksum(&self) -> u64
 	{
-		self.sprite.tick();
-
-		let gamepad = unsafe { *GAMEPAD1 };
-		let left = gamepad & BUTTON_LEFT != 0;
-		let right = gamepad & BUTTON_RIGHT != 0;
-		let up = gamepad & BUTTON_UP != 0;
-		let down = gamepad & BUTTON_DOWN != 0;
-		let speed = 1;
-
-		if self.is_dead
+		let mut checksum = Checksum::new();
+		checksum.i32(self.preset.index() as i32);
+		checksum.u64(self.rng.get_seed());
+		checksum.i32(self.little_guys.len() as i32);
+		for little_guy in &self.little_guys
 		{
-			// Nothing
+			little_guy.hash_into(&mut checksum);
 		}
-		else if left && !right
+		checksum.i32(self.balls.len() as i32);
+		for ball in &self.balls
 		{
-			self.sprite.run_left();
-			self.x -= speed;
+			ball.hash_into(&mut checksum);
 		}
-		else if right && !left
+		checksum.i32(self.score);
+		checksum.i32(self.ticks);
+		checksum.i32(self.time_until_next_ball);
+		checksum.u64(self.recording.num_frames() as u64);
+		checksum.i32(self.recording.movement.index() as i32);
+		checksum.i32(self.recording.num_players as i32);
+		checksum.bool(self.playback.is_some());
+		if let Some(playback) = &self.playback
 		{
-			self.sprite.run_right();
-			self.x += speed;
+			playback.hash_into(&mut checksum);
 		}
-		else if up && !down
+		checksum.i32(self.high_score_rank.map_or(-1, |rank| rank as i32));
+		for count in self.balls_dodged
 		{
-			self.sprite.run_up();
-			self.y -= speed;
+			checksum.i32(count);
 		}
-		else if down && !up
+		checksum.i32(self.closest_call.map_or(-1, Fixed::to_bits));
+		checksum.bool(self.is_waiting_for_release);
+		checksum.bytes(&[self.held_back]);
+		checksum.i32(self.time_held_back);
+		checksum.bool(self.standings.is_some());
+		if let Some(standings) = self.standings
 		{
-			self.sprite.run_down();
-			self.y += speed;
+			checksum.i32(standings.num_players as i32);
+			checksum.bool(standings.is_teams);
+			checksum.i32(standings.best_of);
+			checksum.i32(standings.rounds_played);
+			for wins in standings.wins
+			{
+				checksum.i32(wins);
+			}
 		}
-		else
+		checksum.i32(self.time_until_scoreboard);
+		checksum.bool(self.is_practice);
+		checksum.i32(self.rewind_points.len() as i32);
+		for rewind_point in &self.rewind_points
 		{
-			self.sprite.idle();
+			checksum.i32(rewind_point.state.len() as i32);
+			checksum.bytes(&rewind_point.state);
+			checksum.u64(rewind_point.num_frames as u64);
 		}
+		checksum.finish()
+	}
 
-		let is_cheating = (gamepad & BUTTON_2) != 0;
-		if !self.is_dead && !is_cheating
+	/// A fresh level that continues where the random number generator is.
+	pub fn restarted(&self) -> Level
+	{
+		let Recording {
+			movement,
+			num_players,
+			num_lives,
+			..
+		} = self.recording;
+		let seed = seed_code::truncate(self.rng.get_seed());
+		match self.standings
 		{
-			if self.x < (PADDING_SIZE as i32) + 5
-				|| self.x > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 5
-				|| self.y < (BANNER_HEIGHT as i32) + (PADDING_SIZE as i32) + 3
-				|| self.y > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 3
+			Some(standings) => Level::versus(seed, movement, standings),
+			None =>
 			{
-				self.is_dead = true;
-				self.sprite.die();
+				let level =
+					Level::new(seed, self.preset, movement, num_players);
+				Level {
+					is_practice: self.is_practice,
+					..level.with_lives(num_lives)
+				}
 			}
 		}
 	}
 
-	pub fn kill(&mut self)
+	pub fn can_rewind(&self) -> bool
 	{
-		self.is_dead = true;
-		self.sprite.die();
+		!self.rewind_points.is_empty()
 	}
 
-	pub fn is_alive(&self) -> bool
+	/// Goes back to the second most recent rewind point, because the most
+	/// recent one may have been only a moment ago.
+	pub fn rewind(&mut self) -> Option<()>
 	{
-		!self.is_dead
+		let index = self
+			.rewind_points
+			.len()
+			.checked_sub(2)
+			.or_else(|| self.rewind_points.len().checked_sub(1))?;
+		let RewindPoint { state, num_frames } = &self.rewind_points[index];
+		// The recording is moved over rather than copied.
+		let Recording {
+			seed,
+			preset,
+			movement,
+			num_players,
+			..
+		} = self.recording;
+		let placeholder = Recording::new(seed, preset, movement, num_players);
+		let mut level =
+			Level::read_state(&mut Reader::new(state), placeholder)?;
+		std::mem::swap(&mut level.recording, &mut self.recording);
+		level.recording.truncate(*num_frames);
+		self.rewind_points.truncate(index + 1);
+		level.rewind_points = std::mem::take(&mut self.rewind_points);
+		level.is_waiting_for_release = true;
+		*self = level;
+		Some(())
 	}
 
-	pub fn draw(&self)
+	/// Everything except the rewind points, so that the level can be
+	/// suspended and continue exactly where it left off.
+	pub fn write(&self, writer: &mut Writer)
 	{
-		self.sprite.draw(self.x, self.y);
+		self.recording.write(writer);
+		self.write_state(writer);
 	}
-}
 
-struct Ball
-{
-	x: i32,
-	y: i32,
-	hspd: i32,
-	vspd: i32,
-	warning_time: i32,
-	time_between_warning_shots: i32,
-	is_gone: bool,
-}
+	/// Like `write()`, but without the gamepads recorded so far, which
+	/// means the run can be continued but no longer replayed.
+	pub fn write_without_recording(&self, writer: &mut Writer)
+	{
+		self.recording.write_without_frames(writer);
+		self.write_state(writer);
+	}
 
-impl Ball
-{
-	pub fn new(
-		is_horizontal: bool,
-		base_speed: i32,
-		bonus_speed: i32,
-		warning_time: i32,
-		rng: &mut fastrand::Rng,
-	) -> Self
+	pub fn read(reader: &mut Reader) -> Option<Self>
 	{
-		let speed = base_speed + bonus_speed;
-		let num_warning_shots = 2 + 2 * bonus_speed;
-		let time_between_warning_shots =
-			std::cmp::max(1, warning_time / num_warning_shots);
+		let recording = Recording::read(reader)?;
+		Self::read_state(reader, recording)
+	}
 
-		let minx = (PADDING_SIZE as i32) + 5;
-		let miny = (BANNER_HEIGHT as i32) + (PADDING_SIZE as i32) + 5;
-		let maxx = (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 5;
-		let maxy = (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 5;
-		match rng.bool()
-		{
-			true if !is_horizontal => Self {
-				x: rng.i32(minx..=maxx),
-				y: miny - 5 - 2,
-				hspd: 0,
-				vspd: speed,
-				warning_time,
-				time_between_warning_shots,
-				is_gone: false,
-			},
-			true if is_horizontal => Self {
-				x: maxx + 5 + 2,
-				y: rng.i32(miny..=maxy),
-				hspd: -speed,
-				vspd: 0,
-				warning_time,
-				time_between_warning_shots,
-				is_gone: false,
-			},
-			false if !is_horizontal => Self {
-				x: rng.i32(minx..=maxx),
-				y: maxy + 5 + 2,
-				hspd: 0,
-				vspd: -speed,
-				warning_time,
-				time_between_warning_shots,
-				is_gone: false,
-			},
-			false if is_horizontal => Self {
-				x: minx - 5 - 2,
-				y: rng.i32(miny..=maxy),
-				hspd: speed,
-				vspd: 0,
-				warning_time,
-				time_between_warning_shots,
-				is_gone: false,
-			},
-			_ => unreachable!(),
+	/// Everything except the recording and the rewind points.
+	fn write_state(&self, writer: &mut Writer)
+	{
+		writer.u8(self.preset.index() as u8);
+		writer.u64(self.rng.get_seed());
+		writer.u8(self.little_guys.len() as u8);
+		for little_guy in &self.little_guys
+		{
+			little_guy.write(writer);
+		}
+		writer.varint(self.balls.len() as i32);
+		for ball in &self.balls
+		{
+			ball.write(writer);
+		}
+		writer.varint(self.score);
+		writer.varint(self.ticks);
+		writer.varint(self.time_until_next_ball);
+		writer.option(self.playback.as_ref(), |writer, playback| {
+			playback.write(writer)
+		});
+		writer.option(self.seed_code, |writer, code| code.write(writer));
+		writer
+			.option(self.high_score_rank, |writer, rank| writer.u8(rank as u8));
+		for count in self.balls_dodged
+		{
+			writer.varint(count);
 		}
+		writer.option(self.closest_call, Writer::fixed);
+		writer.bool(self.is_waiting_for_release);
+		writer.u8(self.held_back);
+		writer.varint(self.time_held_back);
+		writer.option(self.standings, |writer, standings| {
+			standings.write(writer)
+		});
+		writer.varint(self.time_until_scoreboard);
+		writer.bool(self.is_practice);
 	}
 
-	pub fn update(&mut self)
+	fn read_state(reader: &mut Reader, recording: Recording) -> Option<Self>
 	{
-		if self.warning_time > 0
+		let preset = Preset::from_index(reader.u8()? as usize)?;
+		// The state of the rng is its seed, so this picks up exactly
+		// where it left off.
+		let rng = fastrand::Rng::with_seed(reader.u64()?);
+		let num_players = reader.u8()? as usize;
+		if !(1..=MAX_PLAYERS).contains(&num_players)
 		{
-			let freq = (700 + self.hspd * 25 + self.vspd * 75) as u32;
-			if (self.warning_time % self.time_between_warning_shots) == 0
-			{
-				tone(freq, 4 | (4 << 8), 30, TONE_TRIANGLE);
-			}
-			self.warning_time -= 1;
-			if self.warning_time == 0
-			{
-				tone(freq, 12 | (4 << 8), 60, TONE_TRIANGLE);
-			}
-			return;
+			return None;
 		}
-
-		self.x += self.hspd;
-		self.y += self.vspd;
-
-		if (self.hspd < 0 && self.x < (PADDING_SIZE as i32))
-			|| (self.hspd > 0
-				&& self.x > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32))
-			|| (self.vspd < 0
-				&& self.y < (BANNER_HEIGHT as i32) + (PADDING_SIZE as i32))
-			|| (self.vspd > 0
-				&& self.y > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32))
+		let little_guys = (0..num_players)
+			.map(|_| LittleGuy::read(reader))
+			.collect::<Option<Vec<_>>>()?;
+		let num_balls = reader.varint()?;
+		let balls = (0..num_balls)
+			.map(|_| Ball::read(reader, num_players))
+			.collect::<Option<Vec<_>>>()?;
+		let score = reader.varint()?;
+		let ticks = reader.varint()?;
+		let time_until_next_ball = reader.varint()?;
+		if recording.num_players != num_players
 		{
-			self.is_gone = true;
+			return None;
+		}
+		let playback = reader.option(Playback::read)?;
+		let seed_code = reader.option(SeedCode::read)?;
+		let high_score_rank =
+			reader.option(|reader| reader.u8())?.map(usize::from);
+		let mut balls_dodged = [0; NUM_TRAJECTORIES];
+		for count in &mut balls_dodged
+		{
+			*count = reader.varint()?;
+		}
+		let closest_call = reader.option(Reader::fixed)?;
+		let is_waiting_for_release = reader.bool()?;
+		let held_back = reader.u8()? & PAUSE_COMBO;
+		let time_held_back = reader.varint()?;
+		let standings = reader.option(Standings::read)?;
+		if standings.is_some_and(|x| x.num_players != num_players)
+		{
+			return None;
 		}
+		Some(Self {
+			preset,
+			rng,
+			little_guys,
+			balls,
+			score,
+			ticks,
+			time_until_next_ball,
+			recording,
+			playback,
+			seed_code,
+			high_score_rank,
+			balls_dodged,
+			closest_call,
+			is_waiting_for_release,
+			held_back,
+			time_held_back,
+			standings,
+			time_until_scoreboard: reader.varint()?,
+			is_practice: reader.bool()?,
+			rewind_points: Vec::new(),
+		})
+	}
+
+	#[cfg(test)]
+	fn to_bytes(&self) -> Vec<u8>
+	{
+		let mut writer = Writer::new();
+		self.write(&mut writer);
+		writer.into_bytes()
 	}
 
-	pub fn draw(&self)
+	#[cfg(test)]
+	fn from_bytes(bytes: &[u8]) -> Option<Self>
 	{
-		if self.warning_time == 0
+		Self::read(&mut Reader::new(bytes))
+	}
+
+	/// Picks up and throws balls, and decides who wins the round.
+	fn referee(
+		&mut self,
+		platform: &mut dyn Platform,
+		standings: Standings,
+	) -> Option<Transition>
+	{
+		for ball in &mut self.balls
 		{
-			sprites::ball::draw(self.x, self.y);
+			// On a split court, balls that miss roll to a stop at the wall,
+			// where the jailed players can get to them.
+			if standings.is_teams && ball.is_gone
+			{
+				ball.is_gone = false;
+				ball.position = Ball::clamped(ball.position);
+				ball.come_to_rest();
+			}
 		}
-		else
+		self.balls.retain(|ball| !ball.is_gone);
+
+		for player in 0..self.little_guys.len()
 		{
-			if (self.warning_time % self.time_between_warning_shots) * 2
-				>= self.time_between_warning_shots - 2
+			let little_guy = &mut self.little_guys[player];
+			let reach = little_guy.reach();
+			if little_guy.has_pressed_grab && little_guy.has_ball
+			{
+				little_guy.has_ball = false;
+				let direction = little_guy.direction;
+				let ball = Ball::thrown(player, little_guy.position, direction);
+				self.balls.push(ball);
+			}
+			else if little_guy.has_pressed_grab
+			{
+				let loose = self.balls.iter().position(|ball| {
+					ball.is_loose && ball.collider().overlaps(&reach)
+				});
+				if let Some(i) = loose
+				{
+					self.balls.remove(i);
+					little_guy.has_ball = true;
+					platform.tone(500 | (800 << 16), 6, 40, TONE_PULSE2);
+				}
+				else
+				{
+					little_guy.try_catch();
+				}
+			}
+
+			if !little_guy.is_catching()
+			{
+				continue;
+			}
+			let is_rival =
+				|owner: usize| standings.side(owner) != standings.side(player);
+			let catchable = self.balls.iter().position(|ball| {
+				ball.is_in_flight()
+					&& ball.owner.is_some_and(is_rival)
+					&& ball.collider().overlaps(&reach)
+			});
+			if let Some(i) = catchable
+			{
+				let ball = self.balls.remove(i);
+				little_guy.hold_catch();
+				little_guy.has_ball = true;
+				little_guy.play_catch_sound(platform);
+				// A caught throw sends the thrower to jail.
+				if let (true, Some(owner)) = (standings.is_teams, ball.owner)
+				{
+					let side = standings.side(owner);
+					self.little_guys[owner].jail(JAILS[side]);
+				}
+			}
+		}
+
+		let forgiveness = self.preset.hitbox_forgiveness();
+		let mut dropped = Vec::new();
+		for player in 0..self.little_guys.len()
+		{
+			for ball in &mut self.balls
 			{
-				if self.hspd == 0
+				let little_guy = &mut self.little_guys[player];
+				let is_teammate = ball.owner.is_some_and(|owner| {
+					standings.side(owner) == standings.side(player)
+				});
+				let hitbox = little_guy.collider(forgiveness);
+				if !little_guy.is_in_play()
+					|| little_guy.is_invulnerable()
+					|| !ball.is_in_flight()
+					|| is_teammate || !ball.collider().overlaps(&hitbox)
+				{
+					continue;
+				}
+				let owner = ball.owner;
+				ball.come_to_rest();
+				if little_guy.has_ball
 				{
-					sprites::warning_horizontal::draw(self.x, self.y);
+					little_guy.has_ball = false;
+					dropped.push(little_guy.position);
+				}
+				if standings.is_teams
+				{
+					little_guy.jail(JAILS[standings.side(player)]);
+					little_guy.play_hit_sound(platform);
+					// Hitting someone from jail is the way back onto the court.
+					if let Some(owner) = owner
+					{
+						let side = standings.side(owner);
+						self.little_guys[owner].release(HALVES[side]);
+					}
 				}
 				else
 				{
-					sprites::warning_vertical::draw(self.x, self.y);
+					little_guy.hit();
+					little_guy.play_hit_sound(platform);
 				}
 			}
 		}
-	}
 
-	pub fn detect_collision(&self, x: i32, y: i32) -> bool
-	{
-		(self.x - x).abs() < 8 && (self.y - y).abs() < 3
+		// Whoever is out lets go of their ball.
+		for little_guy in &mut self.little_guys
+		{
+			if !little_guy.is_alive() && little_guy.has_ball
+			{
+				little_guy.has_ball = false;
+				dropped.push(little_guy.position);
+			}
+		}
+		for position in dropped
+		{
+			self.balls.push(Ball::loose(Ball::clamped(position)));
+		}
+
+		let mut sides = (0..self.little_guys.len())
+			.filter(|&player| self.little_guys[player].is_in_play())
+			.map(|player| standings.side(player));
+		let first = sides.next();
+		if sides.any(|side| Some(side) != first)
+		{
+			self.ticks += 1;
+			let num_held =
+				self.little_guys.iter().filter(|x| x.has_ball).count();
+			if self.time_until_next_ball > 0
+			{
+				self.time_until_next_ball -= 1;
+			}
+			else if self.balls.len() + num_held < standings.num_players
+			{
+				let (minx, miny, maxx, maxy) = Ball::bounds();
+				// Balls are only dropped onto the court, not into the jails.
+				let margin = if standings.is_teams { JAIL_WIDTH } else { 0 };
+				let x = self.rng.i32((minx + margin)..=(maxx - margin));
+				let y = self.rng.i32(miny..=maxy);
+				self.balls.push(Ball::loose(Vector::from_ints(x, y)));
+				self.time_until_next_ball = VERSUS_DROP_TIME;
+			}
+			None
+		}
+		else if self.time_until_scoreboard > 0
+		{
+			self.time_until_scoreboard -= 1;
+			None
+		}
+		else
+		{
+			let winner = first;
+			let mut standings = standings;
+			standings.record(winner);
+			let next_seed = seed_code::truncate(self.rng.get_seed());
+			let scoreboard = Scoreboard::new(
+				standings,
+				winner,
+				next_seed,
+				self.recording.movement,
+			);
+			Some(Transition::Replace(Scene::Scoreboard(scoreboard)))
+		}
+	}
+
+	/// Holds back X and Z on the first gamepad until it is clear whether they
+	/// are meant for the pause combo. Returns the buttons to act on, and
+	/// whether the combo has been held long enough to pause.
+	fn hold_back(&mut self, input: Input) -> (u8, bool)
+	{
+		let held = input.gamepads[0] & PAUSE_COMBO;
+		let mut released = 0;
+		match self.held_back
+		{
+			0 =>
+			{
+				self.held_back = input.pressed & PAUSE_COMBO;
+				self.time_held_back = 0;
+			}
+			PAUSE_COMBO =>
+			{
+				// Neither button does anything until both are released.
+				if held == 0
+				{
+					self.held_back = 0;
+				}
+			}
+			button =>
+			{
+				self.time_held_back += 1;
+				if held == PAUSE_COMBO
+				{
+					self.held_back = PAUSE_COMBO;
+					self.time_held_back = 0;
+				}
+				else if held & button == 0
+					|| self.time_held_back >= PAUSE_GRACE_TIME
+				{
+					// A quick tap still counts as a press.
+					released = button;
+					self.held_back = 0;
+				}
+			}
+		}
+		if self.held_back == PAUSE_COMBO
+		{
+			// Count the frames that both buttons have been down together.
+			if held == PAUSE_COMBO
+			{
+				self.time_held_back += 1;
+			}
+			else
+			{
+				self.time_held_back = 0;
+			}
+		}
+
+		let gamepad = (input.gamepads[0] & !self.held_back) | released;
+		let is_pausing = self.held_back == PAUSE_COMBO
+			&& self.time_held_back >= PAUSE_HOLD_TIME;
+		if is_pausing
+		{
+			self.held_back = 0;
+		}
+		(gamepad, is_pausing)
+	}
+
+	/// The run ends once every little guy is out.
+	fn is_anyone_alive(&self) -> bool
+	{
+		self.little_guys.iter().any(LittleGuy::is_alive)
+	}
+}
+
+fn write_rect(writer: &mut Writer, rect: Rect)
+{
+	writer.varint(rect.x);
+	writer.varint(rect.y);
+	writer.varint(rect.width);
+	writer.varint(rect.height);
+}
+
+fn read_rect(reader: &mut Reader) -> Option<Rect>
+{
+	Some(Rect {
+		x: reader.varint()?,
+		y: reader.varint()?,
+		width: reader.varint()?,
+		height: reader.varint()?,
+	})
+}
+
+/// Where the alive little guy closest to the given position is, if any.
+fn nearest_position(little_guys: &[LittleGuy], position: Vector) -> Vector
+{
+	little_guys
+		.iter()
+		.filter(|little_guy| little_guy.is_alive())
+		.map(|little_guy| little_guy.position)
+		.min_by_key(|&other| (other - position).length())
+		.unwrap_or(little_guys[0].position)
+}
+
+struct LittleGuy
+{
+	position: Vector,
+	velocity: Vector,
+	movement: Movement,
+	sprite: sprites::little_guy::Animation,
+	colors: u16,
+	lives: i32,
+	invulnerable_time: i32,
+	direction: (i32, i32),
+	dash_velocity: Vector,
+	dash_time: i32,
+	dash_cooldown: i32,
+	previous_gamepad: u8,
+	is_dead: bool,
+	/// Where the little guy is kept, instead of dying when leaving the arena.
+	court: Option<Rect>,
+	is_jailed: bool,
+	/// In versus rounds, Z picks up and throws balls instead.
+	can_throw: bool,
+	/// Whether Z was just pressed, to catch, pick up or throw a ball.
+	has_pressed_grab: bool,
+	/// How much longer the hands are out to catch a ball, in ticks.
+	catch_time: i32,
+	/// After catching nothing, the little guy is stuck for a while.
+	recovery_time: i32,
+	has_ball: bool,
+}
+
+const WALKING_SPEED: Fixed = Fixed::from_int(1);
+/// How quickly the little guy speeds up and slows down with momentum.
+const ACCELERATION: Fixed = Fixed::from_ratio(1, 8);
+/// How long the little guy cannot be hit after losing a life, in ticks.
+const INVULNERABILITY_TIME: i32 = 120;
+const DASH_SPEED: Fixed = Fixed::from_int(3);
+/// How long a dash lasts, in ticks. The little guy cannot be hit meanwhile.
+const DASH_TIME: i32 = 8;
+/// How long after a dash the little guy has to wait before dashing again.
+const DASH_COOLDOWN: i32 = 90;
+/// How far beyond their feet the little guy can pick up or catch a ball.
+const REACH: i32 = 3;
+/// How long the hands are out after pressing Z, in ticks.
+const CATCH_WINDOW: i32 = 8;
+/// How long the little guy is stuck after catching nothing, in ticks.
+const RECOVERY_TIME: i32 = 30;
+
+impl LittleGuy
+{
+	pub fn new(num_lives: i32, movement: Movement) -> Self
+	{
+		Self {
+			position: Vector::from_ints(80, 120),
+			velocity: Vector::default(),
+			movement,
+			sprite: sprites::little_guy::Animation::new(),
+			colors: sprites::little_guy::PLAYER_COLORS[0],
+			lives: num_lives,
+			invulnerable_time: 0,
+			direction: (1, 0),
+			dash_velocity: Vector::default(),
+			dash_time: 0,
+			dash_cooldown: 0,
+			// The level is started by pressing X, which should not also
+			// start a dash.
+			previous_gamepad: BUTTON_1,
+			is_dead: false,
+			court: None,
+			is_jailed: false,
+			can_throw: false,
+			has_pressed_grab: false,
+			catch_time: 0,
+			recovery_time: 0,
+			has_ball: false,
+		}
+	}
+
+	/// Spreads the players out along the bottom and tells them apart.
+	pub fn for_player(self, player: usize, num_players: usize) -> Self
+	{
+		let spacing = ARENA.width / (num_players as i32);
+		let x = ARENA.x + spacing * (player as i32) + spacing / 2;
+		Self {
+			position: Vector::new(Fixed::from_int(x), self.position.y),
+			colors: sprites::little_guy::PLAYER_COLORS[player],
+			..self
+		}
+	}
+
+	/// Lines up the team on its half of a split court, facing the other.
+	pub fn for_team(self, player: usize, num_players: usize) -> Self
+	{
+		let team = player % 2;
+		let team_size = (num_players as i32 + 1) / 2;
+		let court = HALVES[team];
+		let x = court.x + court.width / 2;
+		let row = (player / 2) as i32;
+		let y = ARENA.y + ARENA.height * (2 * row + 1) / (2 * team_size);
+		Self {
+			position: Vector::from_ints(x, y),
+			colors: sprites::little_guy::PLAYER_COLORS[player],
+			direction: if team == 0 { (1, 0) } else { (-1, 0) },
+			court: Some(court),
+			..self
+		}
+	}
+
+	pub fn with_throwing(self) -> Self
+	{
+		Self {
+			can_throw: true,
+			..self
+		}
+	}
+
+	pub fn update(&mut self, platform: &mut dyn Platform, gamepad: u8)
+	{
+		self.sprite.tick();
+		if self.invulnerable_time > 0
+		{
+			self.invulnerable_time -= 1;
+		}
+		if self.dash_cooldown > 0
+		{
+			self.dash_cooldown -= 1;
+		}
+		if self.catch_time > 0
+		{
+			self.catch_time -= 1;
+			if self.catch_time == 0
+			{
+				self.recovery_time = RECOVERY_TIME;
+			}
+		}
+		else if self.recovery_time > 0
+		{
+			self.recovery_time -= 1;
+		}
+		let pressed = gamepad & !self.previous_gamepad;
+		self.previous_gamepad = gamepad;
+		self.has_pressed_grab = !self.is_dead && pressed & BUTTON_2 != 0;
+
+		let left = gamepad & BUTTON_LEFT != 0;
+		let right = gamepad & BUTTON_RIGHT != 0;
+		let up = gamepad & BUTTON_UP != 0;
+		let down = gamepad & BUTTON_DOWN != 0;
+		let dx = (right as i32) - (left as i32);
+		let dy = (down as i32) - (up as i32);
+		if dx != 0 || dy != 0
+		{
+			self.direction = (dx, dy);
+		}
+
+		if !self.is_dead
+			&& pressed & BUTTON_1 != 0
+			&& self.dash_cooldown == 0
+			&& !self.is_stuck()
+		{
+			let (dx, dy) = self.direction;
+			self.dash_velocity =
+				Vector::from_ints(dx, dy).with_length(DASH_SPEED);
+			self.dash_time = DASH_TIME;
+			self.dash_cooldown = DASH_TIME + DASH_COOLDOWN;
+			self.sprite.dash(dx, dy);
+			platform.tone(900 | (300 << 16), 10, 40, TONE_NOISE);
+		}
+
+		if self.is_dead
+		{
+			// Nothing
+		}
+		else if self.dash_time > 0
+		{
+			self.dash_time -= 1;
+			self.position += self.dash_velocity;
+		}
+		else if self.is_stuck()
+		{
+			self.velocity = Vector::default();
+		}
+		else
+		{
+			// Diagonals are normalized so they are no faster than straight
+			// lines.
+			let target = Vector::from_ints(dx, dy).with_length(WALKING_SPEED);
+			self.velocity = match self.movement
+			{
+				Movement::Instant => target,
+				Movement::Momentum =>
+				{
+					let change = target - self.velocity;
+					self.velocity
+						+ Vector::new(
+							change.x.clamp_abs(ACCELERATION),
+							change.y.clamp_abs(ACCELERATION),
+						)
+				}
+			};
+			self.position += self.velocity;
+			self.sprite.run(dx, dy);
+		}
+
+		let is_cheating = CAN_WALK_THROUGH_WALLS && (gamepad & BUTTON_2) != 0;
+		if let Some(court) = self.court
+		{
+			self.position += self.footprint().offset_into(court);
+		}
+		else if !self.is_dead
+			&& !is_cheating
+			&& !self.footprint().is_inside(ARENA)
+		{
+			// Leaving the arena ends the run, however many lives are left.
+			self.lives = 0;
+			self.kill();
+		}
+	}
+
+	fn hash_into(&self, checksum: &mut Checksum)
+	{
+		checksum.vector(self.position);
+		checksum.vector(self.velocity);
+		checksum.i32(self.movement as i32);
+		self.sprite.hash_into(checksum);
+		checksum.i32(self.lives);
+		checksum.i32(self.invulnerable_time);
+		checksum.i32(self.direction.0);
+		checksum.i32(self.direction.1);
+		checksum.vector(self.dash_velocity);
+		checksum.i32(self.dash_time);
+		checksum.i32(self.dash_cooldown);
+		checksum.bytes(&[self.previous_gamepad]);
+		checksum.bool(self.is_dead);
+		checksum.bool(self.court.is_some());
+		if let Some(court) = self.court
+		{
+			checksum.i32(court.x);
+			checksum.i32(court.y);
+			checksum.i32(court.width);
+			checksum.i32(court.height);
+		}
+		checksum.bool(self.is_jailed);
+		checksum.bool(self.can_throw);
+		checksum.bool(self.has_pressed_grab);
+		checksum.i32(self.catch_time);
+		checksum.i32(self.recovery_time);
+		checksum.bool(self.has_ball);
+	}
+
+	fn write(&self, writer: &mut Writer)
+	{
+		writer.vector(self.position);
+		writer.vector(self.velocity);
+		writer.u8(self.movement.index() as u8);
+		self.sprite.write(writer);
+		writer.u16(self.colors);
+		writer.varint(self.lives);
+		writer.varint(self.invulnerable_time);
+		writer.varint(self.direction.0);
+		writer.varint(self.direction.1);
+		writer.vector(self.dash_velocity);
+		writer.varint(self.dash_time);
+		writer.varint(self.dash_cooldown);
+		writer.u8(self.previous_gamepad);
+		writer.bool(self.is_dead);
+		writer.option(self.court, write_rect);
+		writer.bool(self.is_jailed);
+		writer.bool(self.can_throw);
+		writer.bool(self.has_pressed_grab);
+		writer.varint(self.catch_time);
+		writer.varint(self.recovery_time);
+		writer.bool(self.has_ball);
+	}
+
+	fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let position = reader.vector()?;
+		let velocity = reader.vector()?;
+		let movement = Movement::from_index(reader.u8()? as usize)?;
+		let sprite = sprites::little_guy::Animation::read(reader)?;
+		let colors = reader.u16()?;
+		let lives = reader.varint()?;
+		let invulnerable_time = reader.varint()?;
+		let direction = (reader.varint()?, reader.varint()?);
+		let directions = -1..=1;
+		if !directions.contains(&direction.0)
+			|| !directions.contains(&direction.1)
+			|| direction == (0, 0)
+		{
+			return None;
+		}
+		Some(Self {
+			position,
+			velocity,
+			movement,
+			sprite,
+			colors,
+			lives,
+			invulnerable_time,
+			direction,
+			dash_velocity: reader.vector()?,
+			dash_time: reader.varint()?,
+			dash_cooldown: reader.varint()?,
+			previous_gamepad: reader.u8()?,
+			is_dead: reader.bool()?,
+			court: reader.option(read_rect)?,
+			is_jailed: reader.bool()?,
+			can_throw: reader.bool()?,
+			has_pressed_grab: reader.bool()?,
+			catch_time: reader.varint()?,
+			recovery_time: reader.varint()?,
+			has_ball: reader.bool()?,
+		})
+	}
+
+	/// Where balls can hit the little guy; forgiveness shrinks it.
+	pub fn collider(&self, forgiveness: i32) -> Collider
+	{
+		sprites::little_guy::HITBOX
+			.shrunk(forgiveness, forgiveness / 2)
+			.at(self.position)
+	}
+
+	fn footprint(&self) -> Collider
+	{
+		sprites::little_guy::FOOTPRINT.at(self.position)
+	}
+
+	/// Where a loose ball has to lie to be picked up.
+	fn reach(&self) -> Collider
+	{
+		sprites::little_guy::FOOTPRINT
+			.shrunk(-REACH, -REACH)
+			.at(self.position)
+	}
+
+	/// Costs a life; losing the last one is fatal.
+	pub fn hit(&mut self)
+	{
+		self.lives -= 1;
+		if self.lives > 0
+		{
+			self.invulnerable_time = INVULNERABILITY_TIME;
+		}
+		else
+		{
+			self.kill();
+		}
+	}
+
+	/// Holds out both hands for a moment, unless they already are.
+	fn try_catch(&mut self)
+	{
+		if !self.is_stuck() && self.dash_time == 0
+		{
+			self.catch_time = CATCH_WINDOW;
+			self.sprite.catch();
+		}
+	}
+
+	fn is_catching(&self) -> bool
+	{
+		self.catch_time > 0
+	}
+
+	/// A catch ends the attempt early, without having to recover.
+	fn hold_catch(&mut self)
+	{
+		self.catch_time = 0;
+	}
+
+	/// Whether the little guy is catching or recovering from a missed catch,
+	/// and cannot move meanwhile.
+	fn is_stuck(&self) -> bool
+	{
+		self.catch_time > 0 || self.recovery_time > 0
+	}
+
+	fn play_catch_sound(&self, platform: &mut dyn Platform)
+	{
+		platform.tone(1000 | (1600 << 16), 4 | (8 << 8), 70, TONE_PULSE1);
+		platform.tone(2000, 6, 30, TONE_TRIANGLE);
+	}
+
+	/// Moves the little guy off the court until they hit someone.
+	fn jail(&mut self, jail: Rect)
+	{
+		self.is_jailed = true;
+		self.move_to(jail);
+	}
+
+	fn release(&mut self, court: Rect)
+	{
+		if self.is_jailed
+		{
+			self.is_jailed = false;
+			self.move_to(court);
+		}
+	}
+
+	/// Keeps to the same height, so that the move is easy to follow.
+	fn move_to(&mut self, court: Rect)
+	{
+		let x = Fixed::from_int(court.x + court.width / 2);
+		self.position = Vector::new(x, self.position.y);
+		self.court = Some(court);
+		self.dash_time = 0;
+		self.invulnerable_time = INVULNERABILITY_TIME;
+	}
+
+	/// Whether the little guy can still be hit, which jailed players cannot.
+	fn is_in_play(&self) -> bool
+	{
+		!self.is_dead && !self.is_jailed
+	}
+
+	/// Whether balls pass right through, either after losing a life or
+	/// while dashing.
+	pub fn is_invulnerable(&self) -> bool
+	{
+		self.invulnerable_time > 0 || self.dash_time > 0
+	}
+
+	/// Draws how far the dash has recharged.
+	fn draw_dash_meter(&self, platform: &mut dyn Platform, x: i32, y: i32)
+	{
+		let width = 32;
+		let charged = if self.dash_cooldown == 0
+		{
+			platform.set_draw_colors(0x44);
+			width
+		}
+		else
+		{
+			platform.set_draw_colors(0x22);
+			width - width * self.dash_cooldown / (DASH_TIME + DASH_COOLDOWN)
+		};
+		if charged > 0
+		{
+			platform.rect(x, y, charged as u32, 2);
+		}
+	}
+
+	fn play_hit_sound(&self, platform: &mut dyn Platform)
+	{
+		if self.is_alive()
+		{
+			platform.tone(250, 5 | (10 << 8), 60, TONE_NOISE);
+			platform.tone(400 | (200 << 16), 15, 60, TONE_PULSE1);
+		}
+		else
+		{
+			platform.tone(250, 5 | (10 << 8), 100, TONE_NOISE);
+			platform.tone(10, 20 | (80 << 8), 100, TONE_PULSE1);
+		}
+	}
+
+	fn kill(&mut self)
+	{
+		self.is_dead = true;
+		self.sprite.die();
+	}
+
+	pub fn is_alive(&self) -> bool
+	{
+		!self.is_dead
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		if self.invulnerable_time > 0 && (self.invulnerable_time / 4) % 2 == 1
+		{
+			return;
+		}
+		let (x, y) = self.position.to_ints();
+		self.sprite.draw(platform, x, y, self.colors);
+		if self.has_ball
+		{
+			sprites::ball::draw_held(platform, x, y - 14);
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Trajectory
+{
+	Horizontal,
+	Vertical,
+	Diagonal,
+	Homing,
+}
+
+pub const NUM_TRAJECTORIES: usize = 4;
+
+impl Trajectory
+{
+	pub const ALL: [Trajectory; NUM_TRAJECTORIES] = [
+		Trajectory::Horizontal,
+		Trajectory::Vertical,
+		Trajectory::Diagonal,
+		Trajectory::Homing,
+	];
+
+	pub fn index(self) -> usize
+	{
+		self as usize
+	}
+
+	pub fn name(self) -> &'static str
+	{
+		match self
+		{
+			Trajectory::Horizontal => "HORIZONTAL",
+			Trajectory::Vertical => "VERTICAL",
+			Trajectory::Diagonal => "DIAGONAL",
+			Trajectory::Homing => "HOMING",
+		}
+	}
+}
+
+/// How long a homing ball keeps steering after its warning, in ticks.
+const HOMING_LIFETIME: i32 = 180;
+const HOMING_SPEED: Fixed = Fixed::from_ratio(3, 2);
+/// How much a homing ball can change its velocity each tick.
+const HOMING_TURN_RATE: Fixed = Fixed::from_ratio(1, 16);
+
+struct Ball
+{
+	trajectory: Trajectory,
+	position: Vector,
+	velocity: Vector,
+	hitbox: Hitbox,
+	bounces_left: i32,
+	homing_time_left: i32,
+	warning_time: i32,
+	time_between_warning_shots: i32,
+	has_bounced: bool,
+	/// The smallest distance to the little guy so far.
+	closest_distance: Option<Fixed>,
+	/// Whether the ball ever overlapped the little guy, even harmlessly.
+	has_touched: bool,
+	is_gone: bool,
+	/// Who threw the ball, if anyone; it cannot hit them.
+	owner: Option<usize>,
+	/// Lying still in the arena, waiting to be picked up.
+	is_loose: bool,
+	/// How much longer a caught ball is shown before it disappears.
+	caught_time: i32,
+}
+
+const THROW_SPEED: Fixed = Fixed::from_int(3);
+/// How long a caught ball is held up before it disappears, in ticks.
+const CAUGHT_TIME: i32 = 30;
+/// A thrown ball is telegraphed much more briefly than one from the edge.
+const THROW_WARNING_TIME: i32 = 10;
+
+impl Ball
+{
+	pub fn new(
+		trajectory: Trajectory,
+		base_speed: Fixed,
+		bonus_speed: Fixed,
+		num_bounces: i32,
+		warning_time: i32,
+		rng: &mut fastrand::Rng,
+	) -> Self
+	{
+		let num_warning_shots = 2 + (bonus_speed * 2).to_int();
+		let time_between_warning_shots =
+			std::cmp::max(1, warning_time / num_warning_shots);
+
+		let (minx, miny, maxx, maxy) = Self::bounds();
+		let centerx = (minx + maxx) / 2;
+		let centery = (miny + maxy) / 2;
+		// The direction of each axis is -1, 0 or 1.
+		let (x, y, hdir, vdir) = match (trajectory, rng.bool())
+		{
+			(Trajectory::Vertical, true) =>
+			{
+				(rng.i32(minx..=maxx), miny - 5 - 2, 0, 1)
+			}
+			(Trajectory::Horizontal, true) =>
+			{
+				(maxx + 5 + 2, rng.i32(miny..=maxy), -1, 0)
+			}
+			(Trajectory::Vertical, false) =>
+			{
+				(rng.i32(minx..=maxx), maxy + 5 + 2, 0, -1)
+			}
+			(Trajectory::Horizontal, false) =>
+			{
+				(minx - 5 - 2, rng.i32(miny..=maxy), 1, 0)
+			}
+			(Trajectory::Diagonal, is_from_corner) =>
+			{
+				let hdir = if rng.bool() { 1 } else { -1 };
+				let vdir = if rng.bool() { 1 } else { -1 };
+				let outside_x =
+					if hdir > 0 { minx - 5 - 2 } else { maxx + 5 + 2 };
+				let outside_y =
+					if vdir > 0 { miny - 5 - 2 } else { maxy + 5 + 2 };
+				if is_from_corner
+				{
+					(outside_x, outside_y, hdir, vdir)
+				}
+				else if rng.bool()
+				{
+					// Enter from the top or bottom edge, heading inwards.
+					let x = rng.i32(minx..=maxx);
+					let hdir = if x < centerx { 1 } else { -1 };
+					(x, outside_y, hdir, vdir)
+				}
+				else
+				{
+					// Enter from the left or right edge, heading inwards.
+					let y = rng.i32(miny..=maxy);
+					let vdir = if y < centery { 1 } else { -1 };
+					(outside_x, y, hdir, vdir)
+				}
+			}
+			(Trajectory::Homing, is_vertical) =>
+			{
+				// Enter from the middle of an edge; steering takes over after
+				// the warning.
+				let dir = if rng.bool() { 1 } else { -1 };
+				if is_vertical
+				{
+					let y = if dir > 0 { miny - 5 - 2 } else { maxy + 5 + 2 };
+					(centerx, y, 0, dir)
+				}
+				else
+				{
+					let x = if dir > 0 { minx - 5 - 2 } else { maxx + 5 + 2 };
+					(x, centery, dir, 0)
+				}
+			}
+		};
+		let is_homing = trajectory == Trajectory::Homing;
+		let speed = if is_homing
+		{
+			HOMING_SPEED
+		}
+		else
+		{
+			base_speed + bonus_speed
+		};
+		Self {
+			trajectory,
+			position: Vector::from_ints(x, y),
+			velocity: Vector::new(speed * hdir, speed * vdir),
+			hitbox: if is_homing
+			{
+				sprites::ball::HOMING_HITBOX
+			}
+			else
+			{
+				sprites::ball::HITBOX
+			},
+			bounces_left: num_bounces,
+			homing_time_left: if is_homing { HOMING_LIFETIME } else { 0 },
+			warning_time,
+			time_between_warning_shots,
+			has_bounced: false,
+			closest_distance: None,
+			has_touched: false,
+			is_gone: false,
+			owner: None,
+			is_loose: false,
+			caught_time: 0,
+		}
+	}
+
+	pub fn loose(position: Vector) -> Self
+	{
+		Self {
+			trajectory: Trajectory::Horizontal,
+			position,
+			velocity: Vector::default(),
+			hitbox: sprites::ball::HITBOX,
+			bounces_left: 0,
+			homing_time_left: 0,
+			warning_time: 0,
+			time_between_warning_shots: 1,
+			has_bounced: false,
+			closest_distance: None,
+			has_touched: false,
+			is_gone: false,
+			owner: None,
+			is_loose: true,
+			caught_time: 0,
+		}
+	}
+
+	/// Flies in the direction the thrower is facing after a short warning.
+	pub fn thrown(owner: usize, position: Vector, direction: (i32, i32))
+		-> Self
+	{
+		let (dx, dy) = direction;
+		let trajectory = match direction
+		{
+			(_, 0) => Trajectory::Horizontal,
+			(0, _) => Trajectory::Vertical,
+			_ => Trajectory::Diagonal,
+		};
+		Self {
+			trajectory,
+			velocity: Vector::from_ints(dx, dy).with_length(THROW_SPEED),
+			warning_time: THROW_WARNING_TIME,
+			time_between_warning_shots: THROW_WARNING_TIME / 2,
+			owner: Some(owner),
+			is_loose: false,
+			..Self::loose(position)
+		}
+	}
+
+	/// Whether the ball can hit anyone; thrown balls cannot while they are
+	/// still being telegraphed.
+	fn is_in_flight(&self) -> bool
+	{
+		!self.is_loose
+			&& self.caught_time == 0
+			&& (self.owner.is_none() || self.warning_time == 0)
+	}
+
+	/// Caught balls are worth a bonus instead of the point for leaving.
+	fn is_caught(&self) -> bool
+	{
+		self.caught_time > 0
+	}
+
+	/// Stops the ball in the hands of whoever caught it.
+	fn catch(&mut self, position: Vector)
+	{
+		self.position = position;
+		self.velocity = Vector::default();
+		self.bounces_left = 0;
+		self.homing_time_left = 0;
+		self.caught_time = CAUGHT_TIME;
+		self.has_touched = true;
+	}
+
+	/// Stops the ball where it is, so that it can be picked up again.
+	fn come_to_rest(&mut self)
+	{
+		self.velocity = Vector::default();
+		self.owner = None;
+		self.is_loose = true;
+	}
+
+	fn hash_into(&self, checksum: &mut Checksum)
+	{
+		checksum.i32(self.trajectory.index() as i32);
+		checksum.vector(self.position);
+		checksum.vector(self.velocity);
+		checksum.i32(self.bounces_left);
+		checksum.i32(self.homing_time_left);
+		checksum.i32(self.warning_time);
+		checksum.i32(self.time_between_warning_shots);
+		checksum.bool(self.has_bounced);
+		checksum.i32(self.closest_distance.map_or(-1, Fixed::to_bits));
+		checksum.bool(self.has_touched);
+		checksum.bool(self.is_gone);
+		checksum.i32(self.owner.map_or(-1, |owner| owner as i32));
+		checksum.bool(self.is_loose);
+		checksum.i32(self.caught_time);
+	}
+
+	fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.trajectory.index() as u8);
+		writer.vector(self.position);
+		writer.vector(self.velocity);
+		writer.varint(self.bounces_left);
+		writer.varint(self.homing_time_left);
+		writer.varint(self.warning_time);
+		writer.varint(self.time_between_warning_shots);
+		writer.bool(self.has_bounced);
+		writer.option(self.closest_distance, Writer::fixed);
+		writer.bool(self.has_touched);
+		writer.bool(self.is_gone);
+		writer.option(self.owner, |writer, owner| writer.u8(owner as u8));
+		writer.bool(self.is_loose);
+		writer.varint(self.caught_time);
+	}
+
+	/// The hitbox is not stored, because only homing balls have a
+	/// different one.
+	fn read(reader: &mut Reader, num_players: usize) -> Option<Self>
+	{
+		let trajectory = *Trajectory::ALL.get(reader.u8()? as usize)?;
+		let position = reader.vector()?;
+		let velocity = reader.vector()?;
+		let bounces_left = reader.varint()?;
+		let homing_time_left = reader.varint()?;
+		let warning_time = reader.varint()?;
+		let time_between_warning_shots = reader.varint()?;
+		if time_between_warning_shots < 1
+		{
+			return None;
+		}
+		let has_bounced = reader.bool()?;
+		let closest_distance = reader.option(Reader::fixed)?;
+		let has_touched = reader.bool()?;
+		let is_gone = reader.bool()?;
+		let owner = reader.option(|reader| reader.u8())?.map(usize::from);
+		if owner.is_some_and(|owner| owner >= num_players)
+		{
+			return None;
+		}
+		Some(Self {
+			trajectory,
+			position,
+			velocity,
+			hitbox: if trajectory == Trajectory::Homing
+			{
+				sprites::ball::HOMING_HITBOX
+			}
+			else
+			{
+				sprites::ball::HITBOX
+			},
+			bounces_left,
+			homing_time_left,
+			warning_time,
+			time_between_warning_shots,
+			has_bounced,
+			closest_distance,
+			has_touched,
+			is_gone,
+			owner,
+			is_loose: reader.bool()?,
+			caught_time: reader.varint()?,
+		})
+	}
+
+	/// The nearest position at which the ball is fully inside the arena.
+	fn clamped(position: Vector) -> Vector
+	{
+		let (minx, miny, maxx, maxy) = Self::bounds();
+		let (x, y) = position.to_ints();
+		Vector::from_ints(x.clamp(minx, maxx), y.clamp(miny, maxy))
+	}
+
+	/// The area in which balls are fully inside the arena.
+	fn bounds() -> (i32, i32, i32, i32)
+	{
+		let minx = (PADDING_SIZE as i32) + 5;
+		let miny = (BANNER_HEIGHT as i32) + (PADDING_SIZE as i32) + 5;
+		let maxx = (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 5;
+		let maxy = (SCREEN_SIZE as i32) - (PADDING_SIZE as i32) - 5;
+		(minx, miny, maxx, maxy)
+	}
+
+	pub fn update(&mut self, platform: &mut dyn Platform, target: Vector)
+	{
+		if self.is_loose
+		{
+			return;
+		}
+
+		if self.caught_time > 1
+		{
+			self.caught_time -= 1;
+			return;
+		}
+		else if self.caught_time > 0
+		{
+			// The time stays up, so that a caught ball is never mistaken for
+			// one that left the arena.
+			self.is_gone = true;
+			return;
+		}
+
+		if self.warning_time > 0
+		{
+			let (hspd, vspd) = self.velocity.to_ints();
+			let freq = (700 + hspd * 25 + vspd * 75) as u32;
+			if (self.warning_time % self.time_between_warning_shots) == 0
+			{
+				platform.tone(freq, 4 | (4 << 8), 30, TONE_TRIANGLE);
+			}
+			self.warning_time -= 1;
+			if self.warning_time == 0
+			{
+				platform.tone(freq, 12 | (4 << 8), 60, TONE_TRIANGLE);
+			}
+			return;
+		}
+
+		if self.homing_time_left > 0
+		{
+			self.homing_time_left -= 1;
+			self.steer(target);
+		}
+
+		self.position += self.velocity;
+
+		self.has_bounced = false;
+		if self.bounces_left > 0
+		{
+			let (minx, miny, maxx, maxy) = Self::bounds();
+			let min = Vector::from_ints(minx, miny);
+			let max = Vector::from_ints(maxx, maxy);
+			let Vector { x, y } = self.position;
+			let Vector { x: hspd, y: vspd } = self.velocity;
+			if (hspd < Fixed::ZERO && x < min.x)
+				|| (hspd > Fixed::ZERO && x > max.x)
+			{
+				let wall = if hspd < Fixed::ZERO { min.x } else { max.x };
+				self.position.x = wall * 2 - x;
+				self.velocity.x = -hspd;
+				self.has_bounced = true;
+			}
+			if (vspd < Fixed::ZERO && y < min.y)
+				|| (vspd > Fixed::ZERO && y > max.y)
+			{
+				let wall = if vspd < Fixed::ZERO { min.y } else { max.y };
+				self.position.y = wall * 2 - y;
+				self.velocity.y = -vspd;
+				self.has_bounced = true;
+			}
+			if self.has_bounced
+			{
+				self.bounces_left -= 1;
+				platform.tone(
+					(300 + 50 * self.bounces_left as u32) | (600 << 16),
+					6,
+					40,
+					TONE_PULSE2,
+				);
+			}
+			return;
+		}
+
+		// A homing ball may swing past the walls while it is still steering.
+		if self.homing_time_left > 0
+		{
+			return;
+		}
+
+		let (x, y) = self.position.to_ints();
+		let Vector { x: hspd, y: vspd } = self.velocity;
+		if (hspd < Fixed::ZERO && x < (PADDING_SIZE as i32))
+			|| (hspd > Fixed::ZERO
+				&& x > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32))
+			|| (vspd < Fixed::ZERO
+				&& y < (BANNER_HEIGHT as i32) + (PADDING_SIZE as i32))
+			|| (vspd > Fixed::ZERO
+				&& y > (SCREEN_SIZE as i32) - (PADDING_SIZE as i32))
+		{
+			self.is_gone = true;
+		}
+	}
+
+	/// Turns the velocity towards the target without changing the speed.
+	fn steer(&mut self, target: Vector)
+	{
+		let desired = (target - self.position).with_length(HOMING_SPEED);
+		let steering = desired - self.velocity;
+		self.velocity.x += steering.x.clamp_abs(HOMING_TURN_RATE);
+		self.velocity.y += steering.y.clamp_abs(HOMING_TURN_RATE);
+		self.velocity = self.velocity.with_length(HOMING_SPEED);
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		let (x, y) = self.position.to_ints();
+		if self.caught_time > 0
+		{
+			if (self.caught_time / 4) % 2 == 0
+			{
+				sprites::ball::draw_held(platform, x, y - 14);
+			}
+		}
+		else if self.warning_time == 0
+		{
+			let colors = if self.homing_time_left > 0
+			{
+				sprites::ball::HOMING_COLORS
+			}
+			else if self.bounces_left > 0
+			{
+				sprites::ball::BOUNCING_COLORS
+			}
+			else
+			{
+				sprites::ball::COLORS
+			};
+			sprites::ball::draw(platform, x, y, colors);
+		}
+		else
+		{
+			if (self.warning_time % self.time_between_warning_shots) * 2
+				>= self.time_between_warning_shots - 2
+			{
+				let Vector { x: hspd, y: vspd } = self.velocity;
+				if self.homing_time_left > 0
+				{
+					sprites::warning_homing::draw(platform, x, y);
+				}
+				else if hspd == Fixed::ZERO
+				{
+					sprites::warning_horizontal::draw(platform, x, y);
+				}
+				else if vspd == Fixed::ZERO
+				{
+					sprites::warning_vertical::draw(platform, x, y);
+				}
+				else
+				{
+					let is_flipped =
+						(hspd > Fixed::ZERO) == (vspd > Fixed::ZERO);
+					sprites::warning_diagonal::draw(platform, x, y, is_flipped);
+				}
+			}
+		}
+	}
+
+	pub fn collider(&self) -> Collider
+	{
+		self.hitbox.at(self.position)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::difficulty::MAX_LIVES;
+	use crate::headless::Headless;
+	use crate::test_support::{random_gamepad, wander};
+	use crate::Game;
+
+	fn wander_until_over(
+		level: &mut Level,
+		console: &mut Headless,
+		context: &mut Context,
+	)
+	{
+		let mut frame = 0;
+		while level.is_anyone_alive() && frame < 100_000
+		{
+			level.update(console, Input::new(wander(frame), 0), context);
+			frame += 1;
+		}
+	}
+
+	#[test]
+	fn test_replay_reproduces_run()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(42, Preset::Normal, Movement::Instant, 1);
+		wander_until_over(&mut level, &mut console, &mut context);
+		assert!(!level.is_anyone_alive());
+		assert_eq!(context.save_data.high_scores[1].entries().len(), 1);
+		let num_dodged: i32 = level.balls_dodged.iter().sum();
+		assert!(num_dodged > 0 && num_dodged <= level.score);
+		assert!(level.closest_call.is_some());
+
+		// Live input must be ignored while the recording lasts.
+		let input = Input::new(BUTTON_LEFT | BUTTON_UP, 0);
+		let mut replayed = Level::replay(&level.recording);
+		while replayed.is_anyone_alive()
+		{
+			replayed.update(&mut console, input, &mut context);
+		}
+		assert_eq!(replayed.ticks, level.ticks);
+		assert_eq!(replayed.score, level.score);
+		assert_eq!(replayed.recording, level.recording);
+		assert_eq!(replayed.rng.get_seed(), level.rng.get_seed());
+		assert_eq!(replayed.balls_dodged, level.balls_dodged);
+		assert_eq!(context.save_data.high_scores[1].entries().len(), 1);
+	}
+
+	#[test]
+	fn test_bouncing_ball_leaves_after_its_bounces()
+	{
+		let mut console = Headless::new();
+		let mut rng = fastrand::Rng::with_seed(7);
+		let mut ball = Ball::new(
+			Trajectory::Diagonal,
+			Fixed::from_ratio(5, 2),
+			Fixed::ZERO,
+			2,
+			10,
+			&mut rng,
+		);
+		let mut num_bounces = 0;
+		let mut frame = 0;
+		while !ball.is_gone && frame < 1000
+		{
+			ball.update(&mut console, Vector::from_ints(80, 80));
+			if ball.has_bounced
+			{
+				num_bounces += 1;
+			}
+			let (minx, miny, maxx, maxy) = Ball::bounds();
+			if ball.bounces_left > 0 && ball.warning_time == 0
+			{
+				let (x, y) = ball.position.to_ints();
+				assert!(x >= minx - 7 && x <= maxx + 7);
+				assert!(y >= miny - 7 && y <= maxy + 7);
+			}
+			frame += 1;
+		}
+		assert!(ball.is_gone);
+		assert_eq!(num_bounces, 2);
+	}
+
+	#[test]
+	fn test_homing_ball_tracks_target_then_leaves()
+	{
+		let mut console = Headless::new();
+		let mut rng = fastrand::Rng::with_seed(3);
+		let mut ball = Ball::new(
+			Trajectory::Homing,
+			Fixed::from_int(1),
+			Fixed::ZERO,
+			0,
+			10,
+			&mut rng,
+		);
+		let target = Vector::from_ints(40, 130);
+		let mut closest = Fixed::from_int(1000);
+		let mut frame = 0;
+		while !ball.is_gone && frame < 1000
+		{
+			ball.update(&mut console, target);
+			let distance = (ball.position - target).length();
+			closest = std::cmp::min(closest, distance);
+			frame += 1;
+		}
+		assert!(ball.is_gone);
+		assert!(frame > HOMING_LIFETIME);
+		assert!(closest < Fixed::from_int(8));
+	}
+
+	#[test]
+	fn test_only_the_last_life_is_fatal()
+	{
+		let mut little_guy = LittleGuy::new(2, Movement::Instant);
+		little_guy.hit();
+		assert!(little_guy.is_alive());
+		assert!(little_guy.is_invulnerable());
+		for _ in 0..INVULNERABILITY_TIME
+		{
+			little_guy.update(&mut Headless::new(), 0);
+		}
+		assert!(!little_guy.is_invulnerable());
+		little_guy.hit();
+		assert!(!little_guy.is_alive());
+		assert_eq!(little_guy.lives, 0);
+	}
+
+	#[test]
+	fn test_chosen_lives_are_replayed_but_not_ranked()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level =
+			Level::new(42, Preset::Normal, Movement::Instant, 1).with_lives(3);
+		assert_eq!(level.little_guys[0].lives, 3);
+		wander_until_over(&mut level, &mut console, &mut context);
+		assert!(!level.is_anyone_alive());
+		assert_eq!(context.save_data.high_scores[1].entries().len(), 0);
+
+		let mut replayed = Level::replay(&level.recording);
+		assert_eq!(replayed.little_guys[0].lives, 3);
+		while replayed.is_anyone_alive()
+		{
+			replayed.update(&mut console, Input::default(), &mut context);
+		}
+		assert_eq!(replayed.ticks, level.ticks);
+		assert_eq!(replayed.score, level.score);
+		assert_eq!(replayed.recording, level.recording);
+	}
+
+	#[test]
+	fn test_dash_has_cooldown_and_invulnerability()
+	{
+		let mut console = Headless::new();
+		let mut little_guy = LittleGuy::new(1, Movement::Instant);
+		little_guy.update(&mut console, 0);
+		little_guy.update(&mut console, BUTTON_1 | BUTTON_LEFT);
+		assert!(little_guy.is_invulnerable());
+		for _ in 1..DASH_TIME
+		{
+			little_guy.update(&mut console, BUTTON_1);
+		}
+		assert!(!little_guy.is_invulnerable());
+		let (x, _) = little_guy.position.to_ints();
+		assert_eq!(x, 80 - DASH_TIME * DASH_SPEED.to_int());
+
+		// Holding or pressing the button again does nothing until recharged.
+		little_guy.update(&mut console, 0);
+		little_guy.update(&mut console, BUTTON_1);
+		assert!(!little_guy.is_invulnerable());
+		for _ in 0..DASH_COOLDOWN
+		{
+			little_guy.update(&mut console, 0);
+		}
+		little_guy.update(&mut console, BUTTON_1);
+		assert!(little_guy.is_invulnerable());
+		assert!(little_guy.is_alive());
+	}
+
+	#[test]
+	fn test_diagonal_movement_is_normalized()
+	{
+		let mut console = Headless::new();
+		let mut straight = LittleGuy::new(1, Movement::Instant);
+		let mut diagonal = LittleGuy::new(1, Movement::Instant);
+		let start = straight.position;
+		for _ in 0..20
+		{
+			straight.update(&mut console, BUTTON_LEFT);
+			diagonal.update(&mut console, BUTTON_LEFT | BUTTON_UP);
+		}
+		let straight_distance = (straight.position - start).length();
+		let diagonal_distance = (diagonal.position - start).length();
+		assert_eq!(straight_distance, Fixed::from_int(20));
+		assert!(diagonal_distance <= straight_distance);
+		assert!(diagonal_distance > Fixed::from_int(19));
+		assert!(diagonal.position.x < start.x && diagonal.position.y < start.y);
+	}
+
+	#[test]
+	fn test_momentum_accelerates_gradually()
+	{
+		let mut console = Headless::new();
+		let mut little_guy = LittleGuy::new(1, Movement::Momentum);
+		little_guy.update(&mut console, BUTTON_RIGHT);
+		assert_eq!(little_guy.velocity.x, ACCELERATION);
+		for _ in 0..20
+		{
+			little_guy.update(&mut console, BUTTON_RIGHT);
+		}
+		assert_eq!(little_guy.velocity.x, WALKING_SPEED);
+		little_guy.update(&mut console, 0);
+		assert_eq!(little_guy.velocity.x, WALKING_SPEED - ACCELERATION);
+	}
+
+	#[test]
+	fn test_holding_z_does_not_save_from_the_walls()
+	{
+		if CAN_WALK_THROUGH_WALLS
+		{
+			return;
+		}
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(7, Preset::Easy, Movement::Instant, 1);
+		let gamepad = BUTTON_LEFT | BUTTON_2;
+		let input = Input::new(gamepad, gamepad);
+		for _ in 0..300
+		{
+			level.update(&mut console, input, &mut context);
+		}
+		assert!(!level.is_anyone_alive());
+	}
+
+	/// Feeds the first gamepad to the level, one state per frame, and
+	/// returns what the last frame asked of the scene stack.
+	fn feed(
+		level: &mut Level,
+		context: &mut Context,
+		gamepads: &[u8],
+	) -> Option<Transition>
+	{
+		let mut console = Headless::new();
+		let mut previous = 0;
+		let mut transition = None;
+		for &gamepad in gamepads
+		{
+			let input = Input::new(gamepad, previous);
+			transition = level.update(&mut console, input, context);
+			previous = gamepad;
+		}
+		transition
+	}
+
+	#[test]
+	fn test_pause_freezes_the_level()
+	{
+		let mut context = Context::new();
+		let mut level = Level::new(7, Preset::Normal, Movement::Instant, 1);
+		feed(&mut level, &mut context, &[0; 30]);
+		let held = [PAUSE_COMBO; PAUSE_HOLD_TIME as usize - 1];
+		assert!(feed(&mut level, &mut context, &held).is_none());
+		let transition = feed(&mut level, &mut context, &[PAUSE_COMBO]);
+		assert!(matches!(
+			transition,
+			Some(Transition::Push(Scene::Pause(_)))
+		));
+		let ticks = level.ticks;
+
+		// After resuming, the level waits for the buttons to be released.
+		feed(&mut level, &mut context, &[PAUSE_COMBO]);
+		assert_eq!(level.ticks, ticks);
+		feed(&mut level, &mut context, &[0]);
+		assert_eq!(level.ticks, ticks + 1);
+	}
+
+	#[test]
+	fn test_pause_combo_holds_back_its_buttons()
+	{
+		// X goes down a frame before Z, which must not dash.
+		let mut context = Context::new();
+		let mut level = Level::new(7, Preset::Normal, Movement::Instant, 1);
+		feed(&mut level, &mut context, &[0; 30]);
+		let mut gamepads = vec![BUTTON_1];
+		gamepads.extend([PAUSE_COMBO; PAUSE_HOLD_TIME as usize]);
+		let transition = feed(&mut level, &mut context, &gamepads);
+		assert!(matches!(
+			transition,
+			Some(Transition::Push(Scene::Pause(_)))
+		));
+		let little_guy = &level.little_guys[0];
+		assert_eq!(little_guy.dash_cooldown, 0);
+		assert_eq!(little_guy.catch_time, 0);
+		assert_eq!(little_guy.recovery_time, 0);
+
+		// Letting go early pauses nothing and does nothing.
+		let mut level = Level::new(7, Preset::Normal, Movement::Instant, 1);
+		feed(&mut level, &mut context, &[0; 30]);
+		let gamepads = [BUTTON_1, PAUSE_COMBO, PAUSE_COMBO, BUTTON_2, 0];
+		assert!(feed(&mut level, &mut context, &gamepads).is_none());
+		assert_eq!(level.little_guys[0].dash_cooldown, 0);
+		assert_eq!(level.little_guys[0].catch_time, 0);
+	}
+
+	#[test]
+	fn test_lone_presses_are_only_delayed()
+	{
+		let mut context = Context::new();
+		for gamepads in [&[BUTTON_1; 8][..], &[BUTTON_1, 0, 0, 0, 0, 0][..]]
+		{
+			let mut level = Level::new(7, Preset::Normal, Movement::Instant, 1);
+			feed(&mut level, &mut context, &[0; 30]);
+			let mut dashed_after = None;
+			for (i, &gamepad) in gamepads.iter().enumerate()
+			{
+				let previous = if i > 0 { gamepads[i - 1] } else { 0 };
+				let mut console = Headless::new();
+				let input = Input::new(gamepad, previous);
+				level.update(&mut console, input, &mut context);
+				if dashed_after.is_none() && level.little_guys[0].dash_time > 0
+				{
+					dashed_after = Some(i as i32);
+				}
+			}
+			let delay = dashed_after.unwrap();
+			assert!(delay <= PAUSE_GRACE_TIME);
+			assert_eq!(
+				level.recording.num_frames(),
+				30 + gamepads.len() as u32
+			);
+		}
+	}
+
+	#[test]
+	fn test_co_op_ends_when_everyone_is_out()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(3, Preset::Easy, Movement::Instant, 2);
+		let (x1, _) = level.little_guys[0].position.to_ints();
+		let (x2, _) = level.little_guys[1].position.to_ints();
+		assert!(x1 < x2);
+
+		// Player 1 walks out of the arena; player 2 stays put.
+		let mut input = Input::default();
+		input.gamepads[0] = BUTTON_DOWN;
+		for _ in 0..40
+		{
+			level.update(&mut console, input, &mut context);
+		}
+		assert!(!level.little_guys[0].is_alive());
+		assert!(level.little_guys[1].is_alive());
+		let ticks = level.ticks;
+		level.update(&mut console, Input::default(), &mut context);
+		assert_eq!(level.ticks, ticks + 1);
+
+		input.gamepads = [0, BUTTON_DOWN, 0, 0];
+		for _ in 0..40
+		{
+			level.update(&mut console, input, &mut context);
+		}
+		assert!(!level.is_anyone_alive());
+		// Co-op runs stay out of the high scores.
+		assert_eq!(context.save_data.high_scores[0].entries().len(), 0);
+
+		// The other players' input is part of the replay.
+		let mut replayed = Level::replay(&level.recording);
+		while replayed.is_anyone_alive()
+		{
+			replayed.update(&mut console, Input::default(), &mut context);
+		}
+		assert_eq!(replayed.ticks, level.ticks);
+	}
+
+	#[test]
+	fn test_level_is_written_after_a_death_has_played_out()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(3, Preset::Easy, Movement::Instant, 2);
+		let mut input = Input::default();
+		input.gamepads[0] = BUTTON_DOWN;
+		for _ in 0..40
+		{
+			level.update(&mut console, input, &mut context);
+		}
+		assert!(!level.little_guys[0].is_alive());
+
+		// Through the death animation and beyond, every frame.
+		for _ in 0..150
+		{
+			level.update(&mut console, Input::default(), &mut context);
+			let bytes = level.to_bytes();
+			let written = Level::from_bytes(&bytes).unwrap();
+			assert_eq!(written.to_bytes(), bytes);
+		}
+		assert!(level.little_guys[1].is_alive());
+	}
+
+	#[test]
+	fn test_catch_stops_the_ball()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(1, Preset::Normal, Movement::Instant, 1);
+		level.time_until_next_ball = 1000;
+		let mut ball = Ball::loose(Vector::from_ints(130, 120));
+		ball.is_loose = false;
+		ball.velocity = Vector::from_ints(-2, 0);
+		level.balls.push(ball);
+
+		// Reach out just before the ball arrives.
+		let mut has_tried = false;
+		for _ in 0..60
+		{
+			let is_close = level.balls[0].position.x <= Fixed::from_int(94);
+			let mut input = Input::default();
+			if is_close && !has_tried
+			{
+				input.gamepads[0] = BUTTON_2;
+				has_tried = true;
+			}
+			level.update(&mut console, input, &mut context);
+			if level.balls.is_empty()
+			{
+				break;
+			}
+		}
+		assert!(has_tried);
+		assert_eq!(level.little_guys[0].lives, Preset::Normal.num_lives());
+		assert!(level.balls.is_empty());
+		assert_eq!(level.score, CATCH_BONUS);
+		assert_eq!(level.balls_dodged, [0; NUM_TRAJECTORIES]);
+	}
+
+	#[test]
+	fn test_missed_catch_needs_recovery()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(1, Preset::Easy, Movement::Instant, 1);
+		level.time_until_next_ball = 1000;
+		let start = level.little_guys[0].position;
+
+		let mut input = Input::default();
+		input.gamepads[0] = BUTTON_2;
+		level.update(&mut console, input, &mut context);
+		input.gamepads[0] = BUTTON_LEFT | BUTTON_1;
+		for _ in 1..(CATCH_WINDOW + RECOVERY_TIME)
+		{
+			level.update(&mut console, input, &mut context);
+			assert_eq!(level.little_guys[0].position, start);
+		}
+		level.update(&mut console, input, &mut context);
+		assert!(level.little_guys[0].position.x < start.x);
+	}
+
+	#[test]
+	fn test_identical_input_gives_identical_checksums()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let teams = Standings::new(4, true, 3);
+		let pairs = [
+			(
+				Level::new(9, Preset::Insane, Movement::Momentum, 3),
+				Level::new(9, Preset::Insane, Movement::Momentum, 3),
+			),
+			(
+				Level::versus(9, Movement::Instant, teams),
+				Level::versus(9, Movement::Instant, teams),
+			),
+		];
+		for (mut a, mut b) in pairs
+		{
+			assert_eq!(a.checksum(), b.checksum());
+			let rng = fastrand::Rng::with_seed(3);
+			for _ in 0..2000
+			{
+				let mut input = Input::default();
+				for gamepad in &mut input.gamepads
+				{
+					*gamepad = random_gamepad(&rng);
+				}
+				a.update(&mut console, input, &mut context);
+				b.update(&mut console, input, &mut context);
+				assert_eq!(a.checksum(), b.checksum());
+			}
+		}
+
+		let a = Level::new(9, Preset::Insane, Movement::Momentum, 3);
+		let b = Level::new(10, Preset::Insane, Movement::Momentum, 3);
+		assert_ne!(a.checksum(), b.checksum());
+		let b = Level::new(9, Preset::Insane, Movement::Momentum, 3);
+		assert_ne!(a.checksum(), b.for_practice().checksum());
+		let mut b = Level::new(9, Preset::Insane, Movement::Momentum, 3);
+		b.recording.movement = Movement::Instant;
+		assert_ne!(a.checksum(), b.checksum());
+
+		let a = Level::versus(9, Movement::Instant, teams);
+		let b = Level::versus(9, Movement::Instant, Standings::new(4, true, 5));
+		assert_ne!(a.checksum(), b.checksum());
+		let mut b = Level::versus(9, Movement::Instant, teams);
+		b.rewind_points.push(RewindPoint {
+			state: Vec::new(),
+			num_frames: 0,
+		});
+		assert_ne!(a.checksum(), b.checksum());
+
+		// Replays hash how far along they are.
+		let mut recording = a.recording.clone();
+		recording.push([0; MAX_PLAYERS]);
+		recording.push([0; MAX_PLAYERS]);
+		let mut b = Level::replay(&recording);
+		let mut c = Level::replay(&recording);
+		b.recording.push([0; MAX_PLAYERS]);
+		c.recording
+			.push(c.playback.as_mut().unwrap().next_gamepads().unwrap());
+		assert_ne!(b.checksum(), c.checksum());
+	}
+
+	#[test]
+	fn test_written_level_continues_identically()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let teams = Standings::new(4, true, 3);
+		let levels = [
+			Level::new(11, Preset::Insane, Movement::Momentum, 3),
+			Level::versus(11, Movement::Instant, teams),
+		];
+		for mut a in levels
+		{
+			let rng = fastrand::Rng::with_seed(5);
+			let random_input = || {
+				let mut input = Input::default();
+				for gamepad in &mut input.gamepads
+				{
+					*gamepad = random_gamepad(&rng);
+				}
+				input
+			};
+			for _ in 0..500
+			{
+				a.update(&mut console, random_input(), &mut context);
+			}
+			let bytes = a.to_bytes();
+			let mut b = Level::from_bytes(&bytes).unwrap();
+			assert_eq!(b.to_bytes(), bytes);
+			assert_eq!(a.checksum(), b.checksum());
+			for _ in 0..1500
+			{
+				let input = random_input();
+				a.update(&mut console, input, &mut context);
+				b.update(&mut console, input, &mut context);
+				assert_eq!(a.checksum(), b.checksum());
+			}
+			assert!(Level::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+		}
+	}
+
+	#[test]
+	fn test_practice_rewinds_to_earlier_points()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level =
+			Level::new(3, Preset::Easy, Movement::Instant, 1).for_practice();
+		let mut recordings = Vec::new();
+		for _ in 0..(12 * REWIND_INTERVAL)
+		{
+			level.update(&mut console, Input::default(), &mut context);
+			recordings.push(level.recording.clone());
+		}
+		assert!(level.is_anyone_alive());
+		assert_eq!(level.rewind_points.len(), MAX_REWIND_POINTS);
+
+		level.rewind().unwrap();
+		assert_eq!(level.ticks, 11 * REWIND_INTERVAL);
+		assert!(level.is_practice);
+		level.rewind().unwrap();
+		assert_eq!(level.ticks, 10 * REWIND_INTERVAL);
+		assert_eq!(level.rewind_points.len(), MAX_REWIND_POINTS - 2);
+		// The recording is cut back to where the run was rewound to.
+		let num_frames = level.recording.num_frames() as usize;
+		assert_eq!(level.recording, recordings[num_frames - 1]);
+
+		let mut normal = Level::new(3, Preset::Easy, Movement::Instant, 1);
+		assert!(!normal.can_rewind());
+		assert!(normal.rewind().is_none());
+	}
+
+	#[test]
+	fn test_failed_rewind_keeps_the_pause_open()
+	{
+		let mut console = Headless::new();
+		let mut level =
+			Level::new(3, Preset::Easy, Movement::Instant, 1).for_practice();
+		level.rewind_points.push(RewindPoint {
+			state: Vec::new(),
+			num_frames: 0,
+		});
+		let mut game = Game::new();
+		game.scenes = vec![
+			Scene::Level(Box::new(level)),
+			Scene::Pause(Pause::new(true)),
+		];
+		game.apply(&mut console, Transition::Rewind);
+		let [Scene::Level(level), Scene::Pause(pause)] = &mut game.scenes[..]
+		else
+		{
+			panic!("the pause menu was closed");
+		};
+		assert_eq!(level.ticks, 0);
+		// REWIND is gone, so RESTART now comes right after RESUME.
+		pause.update(Input::new(BUTTON_DOWN, 0));
+		let transition = pause.update(Input::new(BUTTON_1, 0));
+		assert!(matches!(transition, Some(Transition::Fade(_))));
+	}
+
+	#[test]
+	fn test_rewind_points_stay_small()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let mut level = Level::new(42, Preset::Easy, Movement::Instant, 1)
+			.with_lives(MAX_LIVES)
+			.for_practice();
+		wander_until_over(&mut level, &mut console, &mut context);
+		assert!(level.ticks > 60 * REWIND_INTERVAL);
+		assert_eq!(level.rewind_points.len(), MAX_REWIND_POINTS);
+		let size: usize = level
+			.rewind_points
+			.iter()
+			.map(|point| point.state.len())
+			.sum();
+		assert!(size < 2048, "{} bytes", size);
+	}
+
+	#[test]
+	fn test_versus_throw_wins_the_round()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let standings = Standings::new(2, false, 3);
+		let mut level = Level::versus(5, Movement::Instant, standings);
+		level.time_until_next_ball = 1000;
+		let position = level.little_guys[0].position;
+		level.balls.push(Ball::loose(position));
+
+		// Player 1 picks up the ball and throws it at player 2, who faces
+		// away and stays put.
+		let mut input = Input::default();
+		for gamepad in [BUTTON_2, 0, BUTTON_2]
+		{
+			input.gamepads[0] = gamepad;
+			level.update(&mut console, input, &mut context);
+			assert!(level.little_guys[0].is_alive());
+		}
+		assert!(!level.little_guys[0].has_ball);
+		let mut transition = None;
+		for _ in 0..200
+		{
+			transition =
+				level.update(&mut console, Input::default(), &mut context);
+			if transition.is_some()
+			{
+				break;
+			}
+		}
+		assert!(level.little_guys[0].is_alive());
+		assert!(!level.little_guys[1].is_alive());
+		// The ball that hit lies still, ready for the next throw.
+		assert!(level.balls.iter().any(|ball| ball.is_loose));
+		assert!(matches!(
+			transition,
+			Some(Transition::Replace(Scene::Scoreboard(_)))
+		));
+	}
+
+	#[test]
+	fn test_team_catch_sends_thrower_to_jail()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let standings = Standings::new(2, true, 3);
+		let mut level = Level::versus(5, Movement::Instant, standings);
+		level.time_until_next_ball = 1000;
+		level.little_guys[0].has_ball = true;
+
+		// Player 1 throws across the center line and player 2 catches the
+		// ball just before it hits.
+		let mut input = Input::default();
+		input.gamepads[0] = BUTTON_2;
+		level.update(&mut console, input, &mut context);
+		let mut has_tried = false;
+		for _ in 0..40
+		{
+			let is_close = level.balls.iter().any(|ball| {
+				ball.is_in_flight() && ball.position.x >= Fixed::from_int(91)
+			});
+			let mut input = Input::default();
+			if is_close && !has_tried
+			{
+				input.gamepads[1] = BUTTON_2;
+				has_tried = true;
+			}
+			level.update(&mut console, input, &mut context);
+		}
+		assert!(has_tried);
+		assert!(level.little_guys[1].has_ball);
+		assert!(level.little_guys[1].is_in_play());
+		assert!(level.little_guys[0].is_jailed);
+		assert!(level.little_guys[0].is_alive());
+	}
+
+	#[test]
+	fn test_team_hit_from_jail_gets_back_in()
+	{
+		let mut console = Headless::new();
+		let mut context = Context::new();
+		let standings = Standings::new(2, true, 3);
+		let mut level = Level::versus(5, Movement::Instant, standings);
+		level.time_until_next_ball = 1000;
+		level.little_guys[0].jail(JAILS[0]);
+		level.little_guys[0].has_ball = true;
+		level.little_guys[0].direction = (-1, 0);
+
+		// From behind the other team, player 1 hits player 2 in the back.
+		let mut input = Input::default();
+		input.gamepads[0] = BUTTON_2;
+		let mut transition = level.update(&mut console, input, &mut context);
+		for _ in 0..200
+		{
+			if transition.is_some()
+			{
+				break;
+			}
+			transition =
+				level.update(&mut console, Input::default(), &mut context);
+		}
+		assert!(level.little_guys[0].is_in_play());
+		assert!(level.little_guys[1].is_jailed);
+		assert!(matches!(
+			transition,
+			Some(Transition::Replace(Scene::Scoreboard(_)))
+		));
 	}
 }
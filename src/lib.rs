@@ -6,52 +6,521 @@
 
 mod wasm4;
 
-#[cfg(feature = "buddy-alloc")]
+#[cfg(all(feature = "buddy-alloc", target_arch = "wasm32"))]
 mod alloc;
 
+mod challenge;
+mod checksum;
+mod collision;
+mod controls;
+mod difficulty;
+mod fixed;
+mod game_over;
+#[cfg(target_arch = "wasm32")]
 mod global_state;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+mod highscores;
+mod leaderboard;
 mod level;
 mod menu;
+mod mode_select;
+mod options;
 mod palette;
+mod pause;
+mod platform;
+pub mod replay;
+mod save;
+mod seed_code;
+mod snapshot;
 mod sprites;
+#[cfg(test)]
+mod test_support;
+mod title;
+mod versus;
 
-use global_state::Wrapper;
+pub use controls::MAX_PLAYERS;
+pub use platform::Platform;
+pub use wasm4::{
+	BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP,
+};
+
+use challenge::Challenge;
+use checksum::Checksum;
+use controls::Input;
+use game_over::GameOver;
+use leaderboard::Leaderboard;
 use level::Level;
-use menu::Menu;
+use mode_select::ModeSelect;
+use options::{Options, Settings};
+use pause::Pause;
+use save::SaveData;
+use title::Title;
+use versus::{Scoreboard, VersusSelect};
 
-static GAME: Wrapper<Game> = Wrapper::new(Game::Menu(Menu::new()));
+#[cfg(target_arch = "wasm32")]
+static GAME: global_state::Wrapper<Game> =
+	global_state::Wrapper::new(Game::new());
 
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+fn start()
+{
+	GAME.get_mut().start(&mut platform::Wasm4);
+}
+
+#[cfg(target_arch = "wasm32")]
 #[no_mangle]
 fn update()
 {
-	let game = GAME.get_mut();
-	let outcome = match game
+	GAME.get_mut().update(&mut platform::Wasm4);
+}
+
+/// How long it takes to fade out, and then again to fade back in.
+const FADE_TIME: i32 = 12;
+
+/// Trace a checksum every so many frames, so that the debug consoles of two
+/// machines can be compared line by line.
+const TRACE_CHECKSUMS: bool = cfg!(feature = "debug-checksums");
+const CHECKSUM_INTERVAL: u64 = 60;
+
+pub struct Game
+{
+	/// Only the top scene is updated; the ones below it are frozen.
+	scenes: Vec<Scene>,
+	fade: Option<Fade>,
+	context: Context,
+	previous_gamepad: u8,
+}
+
+/// State that is shared between scenes.
+struct Context
+{
+	save_data: SaveData,
+	settings: Settings,
+	/// Frames since the cart started, used to seed new runs.
+	ticks: u64,
+}
+
+impl Context
+{
+	const fn new() -> Self
+	{
+		Self {
+			save_data: SaveData::new(),
+			settings: Settings::new(),
+			ticks: 0,
+		}
+	}
+}
+
+enum Scene
+{
+	Title(Title),
+	ModeSelect(ModeSelect),
+	Challenge(Challenge),
+	Level(Box<Level>),
+	Pause(Pause),
+	GameOver(Box<GameOver>),
+	HighScores(Leaderboard),
+	Options(Options),
+	VersusSelect(VersusSelect),
+	Scoreboard(Scoreboard),
+}
+
+/// What a scene asks of the stack after it updates.
+enum Transition
+{
+	/// Puts a scene on top of the current one.
+	Push(Scene),
+	/// Removes the current scene, resuming the one below it.
+	Pop,
+	/// Swaps the current scene for another.
+	Replace(Scene),
+	/// Empties the stack and starts over from a single scene.
+	Reset(Scene),
+	/// Fades to black, makes the change, then fades back in.
+	Fade(Box<Transition>),
+	/// Stores the whole game on disk and goes back to the title screen.
+	Suspend,
+	/// Closes the pause menu and takes the level below it back a bit.
+	Rewind,
+	/// Closes the pause menu and starts the level below it over.
+	Restart,
+}
+
+struct Fade
+{
+	/// Made once the screen is completely black.
+	transition: Option<Transition>,
+	ticks: i32,
+}
+
+impl Scene
+{
+	fn update(
+		&mut self,
+		platform: &mut dyn Platform,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		match self
+		{
+			Scene::Title(title) => title.update(input, context),
+			Scene::ModeSelect(mode_select) =>
+			{
+				mode_select.update(input, context)
+			}
+			Scene::Challenge(challenge) => challenge.update(input, context),
+			Scene::Level(level) => level.update(platform, input, context),
+			Scene::Pause(pause) => pause.update(input),
+			Scene::GameOver(game_over) =>
+			{
+				game_over.update(platform, input, context)
+			}
+			Scene::HighScores(leaderboard) => leaderboard.update(input),
+			Scene::Options(options) => options.update(input, context),
+			Scene::VersusSelect(versus_select) =>
+			{
+				versus_select.update(input, context)
+			}
+			Scene::Scoreboard(scoreboard) => scoreboard.update(input),
+		}
+	}
+
+	fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		match self
+		{
+			Scene::Title(title) => title.draw(platform),
+			Scene::ModeSelect(mode_select) =>
+			{
+				mode_select.draw(platform, context)
+			}
+			Scene::Challenge(challenge) => challenge.draw(platform, context),
+			Scene::Level(level) => level.draw(platform),
+			Scene::Pause(pause) => pause.draw(platform),
+			Scene::GameOver(game_over) => game_over.draw(platform),
+			Scene::HighScores(leaderboard) =>
+			{
+				leaderboard.draw(platform, context)
+			}
+			Scene::Options(options) => options.draw(platform, context),
+			Scene::VersusSelect(versus_select) =>
+			{
+				versus_select.draw(platform, context)
+			}
+			Scene::Scoreboard(scoreboard) => scoreboard.draw(platform),
+		}
+	}
+
+	/// Overlays are drawn on top of the scene below them.
+	fn is_overlay(&self) -> bool
+	{
+		matches!(self, Scene::Pause(_))
+	}
+}
+
+impl Game
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			scenes: Vec::new(),
+			fade: None,
+			context: Context::new(),
+			previous_gamepad: 0,
+		}
+	}
+
+	pub fn replay(recording: &replay::Recording) -> Self
+	{
+		Self {
+			scenes: vec![Scene::Level(Box::new(Level::replay(recording)))],
+			..Self::new()
+		}
+	}
+
+	/// Loads the save data and resumes a suspended game, if there is one;
+	/// called once before the first update.
+	pub fn start(&mut self, platform: &mut dyn Platform)
 	{
-		Game::Menu(menu) => menu.update(),
-		Game::Level(level) =>
+		self.context.save_data = SaveData::load(platform);
+		if self.scenes.is_empty()
+		{
+			if let Some(snapshot) = self.context.save_data.suspended.take()
+			{
+				if self.restore(&snapshot).is_some()
+				{
+					// A suspended game can only be resumed once.
+					self.context.save_data.store(platform);
+				}
+				else
+				{
+					// Keep it on the disk rather than lose the run.
+					self.context.save_data.suspended = Some(snapshot);
+					platform.trace("Could not resume the suspended game.");
+				}
+			}
+		}
+		if self.scenes.is_empty()
 		{
-			level.update();
-			None
+			self.scenes.push(Scene::Title(Title::new()));
 		}
-	};
-	match outcome
+	}
+
+	pub fn update(&mut self, platform: &mut dyn Platform)
 	{
-		Some(menu::Outcome::Start { rng_seed }) =>
+		self.context.ticks += 1;
+		let gamepads = std::array::from_fn(|player| platform.gamepad(player));
+		let input = Input {
+			gamepads,
+			..Input::new(gamepads[0], self.previous_gamepad)
+		};
+		self.previous_gamepad = gamepads[0];
+
+		if let Some(fade) = &mut self.fade
+		{
+			fade.ticks += 1;
+			let transition = if fade.ticks == FADE_TIME
+			{
+				fade.transition.take()
+			}
+			else
+			{
+				None
+			};
+			if fade.ticks >= 2 * FADE_TIME
+			{
+				self.fade = None;
+			}
+			if let Some(transition) = transition
+			{
+				self.apply(platform, transition);
+			}
+		}
+		else if let Some(scene) = self.scenes.last_mut()
+		{
+			if let Some(transition) =
+				scene.update(platform, input, &mut self.context)
+			{
+				self.apply(platform, transition);
+			}
+		}
+
+		if TRACE_CHECKSUMS
+			&& self.context.ticks.is_multiple_of(CHECKSUM_INTERVAL)
 		{
-			*game = Game::Level(Level::new(rng_seed));
+			let ticks = self.context.ticks;
+			let text = format!("CHECKSUM {} {:016X}", ticks, self.checksum());
+			platform.trace(&text);
 		}
-		None => (),
+
+		self.draw(platform);
 	}
 
-	match game
+	/// Hashes the frame count and every level on the stack; menus hold
+	/// nothing that netplay depends on.
+	pub fn checksum(&self) -> u64
 	{
-		Game::Menu(menu) => menu.draw(),
-		Game::Level(level) => level.draw(),
+		let mut checksum = Checksum::new();
+		checksum.u64(self.context.ticks);
+		checksum.i32(self.scenes.len() as i32);
+		for scene in &self.scenes
+		{
+			if let Scene::Level(level) = scene
+			{
+				checksum.u64(level.checksum());
+			}
+		}
+		checksum.bool(self.fade.is_some());
+		checksum.finish()
+	}
+
+	fn apply(&mut self, platform: &mut dyn Platform, transition: Transition)
+	{
+		match transition
+		{
+			Transition::Push(scene) => self.scenes.push(scene),
+			Transition::Pop =>
+			{
+				self.scenes.pop();
+			}
+			Transition::Replace(scene) =>
+			{
+				self.scenes.pop();
+				self.scenes.push(scene);
+			}
+			Transition::Reset(scene) =>
+			{
+				self.scenes.clear();
+				self.scenes.push(scene);
+			}
+			Transition::Fade(transition) =>
+			{
+				self.fade = Some(Fade {
+					transition: Some(*transition),
+					ticks: 0,
+				});
+			}
+			Transition::Suspend =>
+			{
+				if self.store_suspended(platform)
+				{
+					let quit = Transition::Reset(Scene::Title(Title::new()));
+					self.apply(platform, Transition::Fade(Box::new(quit)));
+				}
+				else if let Some(Scene::Pause(pause)) = self.scenes.last_mut()
+				{
+					pause.refuse_suspend(platform);
+				}
+			}
+			Transition::Rewind =>
+			{
+				let below = self.scenes.len().checked_sub(2);
+				let is_rewound = match below
+					.and_then(|index| self.scenes.get_mut(index))
+				{
+					Some(Scene::Level(level)) => level.rewind().is_some(),
+					_ => false,
+				};
+				if is_rewound
+				{
+					self.scenes.pop();
+				}
+				else if let Some(Scene::Pause(pause)) = self.scenes.last_mut()
+				{
+					pause.refuse_rewind(platform);
+				}
+			}
+			Transition::Restart =>
+			{
+				self.scenes.pop();
+				if let Some(Scene::Level(level)) = self.scenes.last_mut()
+				{
+					**level = level.restarted();
+				}
+			}
+		}
+		if self.scenes.is_empty()
+		{
+			self.scenes.push(Scene::Title(Title::new()));
+		}
+	}
+
+	fn draw(&self, platform: &mut dyn Platform)
+	{
+		let bottom = self
+			.scenes
+			.iter()
+			.rposition(|scene| !scene.is_overlay())
+			.unwrap_or(0);
+		for scene in &self.scenes[bottom..]
+		{
+			scene.draw(platform, &self.context);
+		}
+
+		if let Some(fade) = &self.fade
+		{
+			let darkness = FADE_TIME - (fade.ticks - FADE_TIME).abs();
+			let palette = palette::SODACAP_PALETTE;
+			platform.set_palette(palette::faded(palette, darkness, FADE_TIME));
+		}
+	}
+}
+
+impl Default for Game
+{
+	fn default() -> Self
+	{
+		Self::new()
 	}
 }
 
-enum Game
+#[cfg(test)]
+mod tests
 {
-	Menu(Menu),
-	Level(Level),
+	use super::*;
+	use headless::Headless;
+	use test_support::{pause, press, random_gamepad, wait_for_fade};
+
+	#[test]
+	fn test_scene_stack()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		game.start(&mut console);
+		assert!(matches!(game.scenes[..], [Scene::Title(_)]));
+
+		press(&mut game, &mut console, BUTTON_1);
+		assert!(matches!(
+			game.scenes[..],
+			[Scene::Title(_), Scene::ModeSelect(_)]
+		));
+		press(&mut game, &mut console, BUTTON_2);
+		assert!(matches!(game.scenes[..], [Scene::Title(_)]));
+
+		press(&mut game, &mut console, BUTTON_1);
+		press(&mut game, &mut console, BUTTON_1);
+		assert!(game.fade.is_some());
+		wait_for_fade(&mut game, &mut console);
+		assert!(matches!(
+			game.scenes[..],
+			[Scene::Title(_), Scene::Level(_)]
+		));
+
+		pause(&mut game, &mut console);
+		assert!(matches!(
+			game.scenes[..],
+			[Scene::Title(_), Scene::Level(_), Scene::Pause(_)]
+		));
+		press(&mut game, &mut console, BUTTON_UP);
+		press(&mut game, &mut console, BUTTON_1);
+		wait_for_fade(&mut game, &mut console);
+		assert!(matches!(game.scenes[..], [Scene::Title(_)]));
+	}
+
+	#[test]
+	fn test_identical_input_gives_identical_checksums()
+	{
+		let mut consoles = [Headless::new(), Headless::new(), Headless::new()];
+		let mut games = [Game::new(), Game::new(), Game::new()];
+		for (game, console) in games.iter_mut().zip(&mut consoles)
+		{
+			game.start(console);
+			press(game, console, BUTTON_1);
+		}
+		// The seed comes from the frame on which the run starts, so lingering
+		// in the menu for a single frame is enough to tell runs apart.
+		games[2].update(&mut consoles[2]);
+		for (game, console) in games.iter_mut().zip(&mut consoles)
+		{
+			press(game, console, BUTTON_1);
+			wait_for_fade(game, console);
+		}
+
+		let rng = fastrand::Rng::with_seed(7);
+		for _ in 0..1200
+		{
+			let gamepad = random_gamepad(&rng);
+			for (game, console) in games.iter_mut().zip(&mut consoles)
+			{
+				console.gamepads[0] = gamepad;
+				game.update(console);
+			}
+			assert_eq!(games[0].checksum(), games[1].checksum());
+		}
+		assert_ne!(games[0].checksum(), games[2].checksum());
+	}
+
+	#[test]
+	fn test_fade_goes_through_black()
+	{
+		let palette = palette::SODACAP_PALETTE;
+		assert_eq!(palette::faded(palette, 0, FADE_TIME), palette);
+		assert_eq!(palette::faded(palette, FADE_TIME, FADE_TIME), [0; 4]);
+		let halfway = palette::faded(palette, FADE_TIME / 2, FADE_TIME);
+		assert_eq!(halfway[3], 0x103b66);
+	}
 }
@@ -4,32 +4,56 @@
 // License: MIT
 //
 
-use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
 use crate::wasm4::*;
 
-pub struct Menu
+/// A vertical list of choices, navigated with up and down.
+pub struct List
 {
-	rng_seed: u64,
+	selection: usize,
+	num_choices: usize,
 }
 
-impl Menu
+impl List
 {
-	pub const fn new() -> Self
+	pub const fn new(num_choices: usize) -> Self
 	{
-		Self { rng_seed: 0 }
+		Self {
+			selection: 0,
+			num_choices,
+		}
+	}
+
+	pub const fn with_selection(num_choices: usize, selection: usize) -> Self
+	{
+		Self {
+			selection,
+			num_choices,
+		}
 	}
 
-	pub fn update(&mut self) -> Option<Outcome>
+	pub fn selection(&self) -> usize
 	{
-		let gamepad = unsafe { *GAMEPAD1 };
+		self.selection
+	}
 
-		self.rng_seed += 1;
+	/// Returns the selected index once X is pressed.
+	pub fn update(&mut self, pressed: u8) -> Option<usize>
+	{
+		if pressed & BUTTON_UP != 0
+		{
+			self.selection =
+				(self.selection + self.num_choices - 1) % self.num_choices;
+		}
+		else if pressed & BUTTON_DOWN != 0
+		{
+			self.selection = (self.selection + 1) % self.num_choices;
+		}
 
-		if gamepad & BUTTON_1 != 0
+		if pressed & BUTTON_1 != 0
 		{
-			Some(Outcome::Start {
-				rng_seed: self.rng_seed,
-			})
+			Some(self.selection)
 		}
 		else
 		{
@@ -37,23 +61,56 @@ impl Menu
 		}
 	}
 
-	pub fn draw(&mut self)
+	pub fn write(&self, writer: &mut Writer)
 	{
-		unsafe {
-			*PALETTE = palette::SODACAP_PALETTE;
-		}
+		writer.u8(self.selection as u8);
+	}
 
-		unsafe { *DRAW_COLORS = 4 }
-		text("DODGEBALL", 10, 10);
+	/// Only the selection is stored, because the choices never change.
+	pub fn read(reader: &mut Reader, num_choices: usize) -> Option<Self>
+	{
+		let selection = reader.u8()? as usize;
+		if selection >= num_choices
+		{
+			return None;
+		}
+		Some(Self::with_selection(num_choices, selection))
+	}
 
-		text("PRESS X TO START", 10, 140);
+	/// Draws one choice per row, marking the selected one.
+	pub fn draw(
+		&self,
+		platform: &mut dyn Platform,
+		names: &[&str],
+		x: i32,
+		y: i32,
+	)
+	{
+		for (i, name) in names.iter().enumerate()
+		{
+			let row = y + 10 * (i as i32);
+			let color = if i == self.selection { 2 } else { 4 };
+			platform.set_draw_colors(color);
+			platform.text(name, x, row);
+			if i == self.selection
+			{
+				platform.text(">", x - 9, row);
+			}
+		}
 	}
 }
 
-pub enum Outcome
+/// Draws a line of text centered horizontally.
+pub fn draw_centered(platform: &mut dyn Platform, text: &str, y: i32)
 {
-	Start
-	{
-		rng_seed: u64
-	},
+	platform.text(text, 80 - 4 * (text.len() as i32), y);
+}
+
+/// Draws a centered choice that can be changed with left and right.
+pub fn draw_carousel(platform: &mut dyn Platform, text: &str, y: i32)
+{
+	let x = 80 - 4 * (text.len() as i32);
+	platform.text(text, x, y);
+	platform.text("<", x - 16, y);
+	platform.text(">", x + 8 * (text.len() as i32) + 8, y);
 }
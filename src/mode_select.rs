@@ -0,0 +1,134 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::{Input, MAX_PLAYERS};
+use crate::level::Level;
+use crate::menu;
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::seed_code;
+use crate::wasm4::*;
+use crate::{Context, Scene, Transition};
+
+/// Picks the difficulty preset for the next run.
+pub struct ModeSelect
+{
+	/// Practice runs can be rewound, but do not count for high scores.
+	is_practice: bool,
+}
+
+impl ModeSelect
+{
+	pub const fn new() -> Self
+	{
+		Self { is_practice: false }
+	}
+
+	pub const fn practice() -> Self
+	{
+		Self { is_practice: true }
+	}
+
+	pub fn update(
+		&mut self,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		let settings = &mut context.settings;
+		if input.pressed & BUTTON_LEFT != 0
+		{
+			settings.preset = settings.preset.previous();
+		}
+		else if input.pressed & BUTTON_RIGHT != 0
+		{
+			settings.preset = settings.preset.next();
+		}
+		else if input.pressed & BUTTON_UP != 0
+		{
+			settings.num_players = settings.num_players % MAX_PLAYERS + 1;
+		}
+		else if input.pressed & BUTTON_DOWN != 0
+		{
+			settings.num_players =
+				(settings.num_players + MAX_PLAYERS - 2) % MAX_PLAYERS + 1;
+		}
+
+		if input.pressed & BUTTON_1 != 0
+		{
+			let seed = seed_code::truncate(context.ticks);
+			let level = Level::new(
+				seed,
+				settings.preset,
+				settings.movement,
+				settings.num_players,
+			)
+			.with_lives(settings.num_lives[settings.preset.index()]);
+			let level = if self.is_practice
+			{
+				level.for_practice()
+			}
+			else
+			{
+				level
+			};
+			let start = Transition::Replace(Scene::Level(Box::new(level)));
+			Some(Transition::Fade(Box::new(start)))
+		}
+		else if input.pressed & BUTTON_2 != 0
+		{
+			Some(Transition::Pop)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.bool(self.is_practice);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let is_practice = reader.bool()?;
+		Some(Self { is_practice })
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		let heading = if self.is_practice
+		{
+			"PRACTICE"
+		}
+		else
+		{
+			"DODGEBALL"
+		};
+		platform.text(heading, 10, 10);
+
+		let preset = context.settings.preset;
+		let high_scores = &context.save_data.high_scores[preset.index()];
+		high_scores.draw(platform, 30, None);
+
+		platform.set_draw_colors(4);
+		menu::draw_carousel(platform, preset.name(), 106);
+		let players = match context.settings.num_players
+		{
+			1 => "1 PLAYER".to_string(),
+			n => format!("{} PLAYERS CO-OP", n),
+		};
+		menu::draw_centered(platform, &players, 118);
+
+		platform.text("PRESS X TO START", 10, 130);
+		platform.text("PRESS Z TO GO BACK", 10, 140);
+	}
+}
@@ -0,0 +1,200 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::{Input, Movement, MAX_PLAYERS};
+use crate::difficulty::{Preset, MAX_LIVES, NUM_PRESETS};
+use crate::menu::{self, List};
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::seed_code::SeedCode;
+use crate::versus::MAX_BEST_OF;
+use crate::wasm4::*;
+use crate::{Context, Transition};
+
+/// Choices that carry over from one run to the next.
+pub struct Settings
+{
+	pub preset: Preset,
+	pub movement: Movement,
+	/// How many gamepads take part in a run.
+	pub num_players: usize,
+	/// How many gamepads take part in a versus match.
+	pub num_rivals: usize,
+	/// Whether versus matches are played in two teams on a split court.
+	pub is_teams: bool,
+	/// How many rounds a versus match lasts at most.
+	pub best_of: i32,
+	/// How many lives a run starts with, for each preset.
+	pub num_lives: [i32; NUM_PRESETS],
+	/// The last code entered for a challenge.
+	pub challenge: SeedCode,
+}
+
+impl Settings
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			preset: Preset::Normal,
+			movement: Movement::Instant,
+			num_players: 1,
+			num_rivals: 2,
+			is_teams: false,
+			best_of: 3,
+			num_lives: [
+				Preset::Easy.num_lives(),
+				Preset::Normal.num_lives(),
+				Preset::Hard.num_lives(),
+				Preset::Insane.num_lives(),
+			],
+			challenge: SeedCode::FIRST,
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.preset.index() as u8);
+		writer.u8(self.movement.index() as u8);
+		writer.u8(self.num_players as u8);
+		writer.u8(self.num_rivals as u8);
+		writer.bool(self.is_teams);
+		writer.varint(self.best_of);
+		for num_lives in self.num_lives
+		{
+			writer.u8(num_lives as u8);
+		}
+		self.challenge.write(writer);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let settings = Self {
+			preset: Preset::from_index(reader.u8()? as usize)?,
+			movement: Movement::from_index(reader.u8()? as usize)?,
+			num_players: reader.u8()? as usize,
+			num_rivals: reader.u8()? as usize,
+			is_teams: reader.bool()?,
+			best_of: reader.varint()?,
+			num_lives: [
+				reader.u8()? as i32,
+				reader.u8()? as i32,
+				reader.u8()? as i32,
+				reader.u8()? as i32,
+			],
+			challenge: SeedCode::read(reader)?,
+		};
+		let players = 1..=MAX_PLAYERS;
+		if !players.contains(&settings.num_players)
+			|| !players.contains(&settings.num_rivals)
+			|| !(1..=MAX_BEST_OF).contains(&settings.best_of)
+			|| settings
+				.num_lives
+				.iter()
+				.any(|x| !(1..=MAX_LIVES).contains(x))
+		{
+			return None;
+		}
+		Some(settings)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Choice
+{
+	Movement,
+	Lives,
+	Back,
+}
+
+impl Choice
+{
+	const ALL: [Choice; 3] = [Choice::Movement, Choice::Lives, Choice::Back];
+}
+
+pub struct Options
+{
+	list: List,
+}
+
+impl Options
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			list: List::new(Choice::ALL.len()),
+		}
+	}
+
+	pub fn update(
+		&mut self,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		let chosen = self.list.update(input.pressed);
+		let selected = Choice::ALL[self.list.selection()];
+		let is_toggled = input.pressed & (BUTTON_LEFT | BUTTON_RIGHT) != 0
+			|| chosen.is_some();
+		let settings = &mut context.settings;
+		if selected == Choice::Movement && is_toggled
+		{
+			settings.movement = settings.movement.next();
+		}
+		else if selected == Choice::Lives && is_toggled
+		{
+			// The lives of the preset that was last chosen.
+			let num_lives = &mut settings.num_lives[settings.preset.index()];
+			*num_lives = if input.pressed & BUTTON_LEFT != 0
+			{
+				(*num_lives + MAX_LIVES - 2) % MAX_LIVES + 1
+			}
+			else
+			{
+				*num_lives % MAX_LIVES + 1
+			};
+		}
+
+		if input.pressed & BUTTON_2 != 0
+			|| chosen == Some(Choice::Back as usize)
+		{
+			Some(Transition::Pop)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		self.list.write(writer);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let list = List::read(reader, Choice::ALL.len())?;
+		Some(Self { list })
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, "OPTIONS", 10);
+
+		let settings = &context.settings;
+		let movement = format!("MOVEMENT: {}", settings.movement.name());
+		let lives = format!(
+			"{} LIVES: {}",
+			settings.preset.name(),
+			settings.num_lives[settings.preset.index()]
+		);
+		let names = [movement.as_str(), lives.as_str(), "BACK"];
+		self.list.draw(platform, &names, 16, 40);
+	}
+}
@@ -13,3 +13,16 @@ pub const SODACAP_PALETTE: [u32; 4] = [
 	0xe8e7cb,
 	0x2176cc,
 ];
+
+/// Darkens every color, from unchanged at `amount == 0` to black at
+/// `amount == total`.
+pub fn faded(palette: [u32; 4], amount: i32, total: i32) -> [u32; 4]
+{
+	let remaining = (total - amount.clamp(0, total)) as u32;
+	palette.map(|color| {
+		let channels =
+			[(color >> 16) & 0xff, (color >> 8) & 0xff, color & 0xff];
+		let [r, g, b] = channels.map(|x| x * remaining / total as u32);
+		(r << 16) | (g << 8) | b
+	})
+}
@@ -0,0 +1,199 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::collision::Rect;
+use crate::controls::Input;
+use crate::level::ARENA;
+use crate::menu::List;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::sprites;
+use crate::title::Title;
+use crate::wasm4::*;
+use crate::{Scene, Transition};
+
+/// Holding X and Z together pauses a level.
+pub const PAUSE_COMBO: u8 = BUTTON_1 | BUTTON_2;
+/// How long X or Z is held back, waiting for the other button to join it in
+/// the pause combo, in ticks.
+pub const PAUSE_GRACE_TIME: i32 = 3;
+/// How long the pause combo must be held, in ticks, so that pressing X and Z
+/// at once in the heat of the moment does not pause.
+pub const PAUSE_HOLD_TIME: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Choice
+{
+	Resume,
+	Rewind,
+	Restart,
+	Suspend,
+	Quit,
+}
+
+impl Choice
+{
+	const ALL: [Choice; 5] = [
+		Choice::Resume,
+		Choice::Rewind,
+		Choice::Restart,
+		Choice::Suspend,
+		Choice::Quit,
+	];
+	const WITHOUT_REWIND: [Choice; 4] = [
+		Choice::Resume,
+		Choice::Restart,
+		Choice::Suspend,
+		Choice::Quit,
+	];
+
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			Choice::Resume => "RESUME",
+			Choice::Rewind => "REWIND",
+			Choice::Restart => "RESTART",
+			Choice::Suspend => "SUSPEND",
+			Choice::Quit => "QUIT",
+		}
+	}
+}
+
+/// An overlay on top of a paused level.
+pub struct Pause
+{
+	list: List,
+	/// Whether the paused level is a practice run with a point to go back to.
+	can_rewind: bool,
+	/// Whether the game was too big to suspend to the disk.
+	has_no_room: bool,
+}
+
+impl Pause
+{
+	pub fn new(can_rewind: bool) -> Self
+	{
+		Self {
+			list: List::new(choices(can_rewind).len()),
+			can_rewind,
+			has_no_room: false,
+		}
+	}
+
+	pub fn update(&mut self, input: Input) -> Option<Transition>
+	{
+		let choices = choices(self.can_rewind);
+		let choice = choices[self.list.update(input.pressed)?];
+		match choice
+		{
+			Choice::Resume => Some(Transition::Pop),
+			Choice::Rewind => Some(Transition::Rewind),
+			Choice::Restart =>
+			{
+				Some(Transition::Fade(Box::new(Transition::Restart)))
+			}
+			Choice::Suspend =>
+			{
+				// Whoever resumes the game should find RESUME selected.
+				self.list = List::new(choices.len());
+				Some(Transition::Suspend)
+			}
+			Choice::Quit =>
+			{
+				let quit = Transition::Reset(Scene::Title(Title::new()));
+				Some(Transition::Fade(Box::new(quit)))
+			}
+		}
+	}
+
+	pub fn refuse_suspend(&mut self, platform: &mut dyn Platform)
+	{
+		let choices = choices(self.can_rewind);
+		let suspend = choices.iter().position(|&x| x == Choice::Suspend);
+		self.list = List::with_selection(choices.len(), suspend.unwrap_or(0));
+		self.has_no_room = true;
+		platform.tone(200, 10, 50, TONE_PULSE1);
+	}
+
+	/// Takes REWIND off the menu when the level could not go back after all.
+	pub fn refuse_rewind(&mut self, platform: &mut dyn Platform)
+	{
+		self.can_rewind = false;
+		self.list = List::new(choices(self.can_rewind).len());
+		platform.tone(200, 10, 50, TONE_PULSE1);
+	}
+
+	/// The choice is stored instead of the selection, because the choices
+	/// depend on whether the level can still be rewound.
+	pub fn write(&self, writer: &mut Writer)
+	{
+		let choices = choices(self.can_rewind);
+		writer.u8(choices[self.list.selection()] as u8);
+	}
+
+	/// A restored level has nothing to rewind to, so the paused level
+	/// decides whether REWIND is still there.
+	pub fn read(reader: &mut Reader, can_rewind: bool) -> Option<Self>
+	{
+		let choice = *Choice::ALL.get(reader.u8()? as usize)?;
+		let choices = choices(can_rewind);
+		let selection = choices.iter().position(|&x| x == choice);
+		Some(Self {
+			list: List::with_selection(choices.len(), selection.unwrap_or(0)),
+			..Self::new(can_rewind)
+		})
+	}
+
+	/// Cuts off any tones that are still playing.
+	pub fn silence(platform: &mut dyn Platform)
+	{
+		for channel in [TONE_PULSE1, TONE_PULSE2, TONE_TRIANGLE, TONE_NOISE]
+		{
+			platform.tone(1, 1, 0, channel);
+		}
+	}
+
+	/// Dims the arena behind the menu.
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		let Rect {
+			x,
+			y,
+			width,
+			height,
+		} = ARENA;
+		sprites::dither::fill(platform, x, y, width, height);
+
+		let choices = choices(self.can_rewind);
+		let height = 26 + 10 * choices.len() as u32;
+		platform.set_draw_colors(0x43);
+		platform.rect(44, 62, 72, height);
+		platform.set_draw_colors(4);
+		platform.text("PAUSED", 56, 68);
+		let names: Vec<&str> = choices
+			.iter()
+			.map(|&choice| match choice
+			{
+				Choice::Suspend if self.has_no_room => "NO ROOM",
+				_ => choice.name(),
+			})
+			.collect();
+		self.list.draw(platform, &names, 58, 84);
+	}
+}
+
+fn choices(can_rewind: bool) -> &'static [Choice]
+{
+	if can_rewind
+	{
+		&Choice::ALL
+	}
+	else
+	{
+		&Choice::WITHOUT_REWIND
+	}
+}
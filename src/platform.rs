@@ -0,0 +1,143 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+#[cfg(target_arch = "wasm32")]
+use crate::wasm4;
+
+/// Everything the game needs from the outside world: input, drawing,
+/// audio and storage. The cart itself runs on `Wasm4`, but the game logic
+/// only ever talks to this trait, so it can also run on the host.
+pub trait Platform
+{
+	/// Player 0 is `GAMEPAD1`, player 1 is `GAMEPAD2` and so on.
+	fn gamepad(&self, player: usize) -> u8;
+
+	fn set_palette(&mut self, palette: [u32; 4]);
+	fn set_draw_colors(&mut self, draw_colors: u16);
+
+	fn blit(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		flags: u32,
+	);
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32);
+	fn hline(&mut self, x: i32, y: i32, len: u32);
+	fn vline(&mut self, x: i32, y: i32, len: u32);
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32);
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32);
+	fn text(&mut self, text: &str, x: i32, y: i32);
+
+	fn tone(&mut self, frequency: u32, duration: u32, volume: u32, flags: u32);
+
+	/// Reads up to `buffer.len()` bytes from persistent storage.
+	fn diskr(&mut self, buffer: &mut [u8]) -> usize;
+	/// Writes up to `data.len()` bytes to persistent storage.
+	fn diskw(&mut self, data: &[u8]) -> usize;
+
+	fn trace(&mut self, text: &str);
+}
+
+/// The real WASM-4 console.
+#[cfg(target_arch = "wasm32")]
+pub struct Wasm4;
+
+#[cfg(target_arch = "wasm32")]
+impl Platform for Wasm4
+{
+	fn gamepad(&self, player: usize) -> u8
+	{
+		let gamepads = [
+			wasm4::GAMEPAD1,
+			wasm4::GAMEPAD2,
+			wasm4::GAMEPAD3,
+			wasm4::GAMEPAD4,
+		];
+		unsafe { *gamepads[player] }
+	}
+
+	fn set_palette(&mut self, palette: [u32; 4])
+	{
+		unsafe {
+			*wasm4::PALETTE = palette;
+		}
+	}
+
+	fn set_draw_colors(&mut self, draw_colors: u16)
+	{
+		unsafe {
+			*wasm4::DRAW_COLORS = draw_colors;
+		}
+	}
+
+	fn blit(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		flags: u32,
+	)
+	{
+		wasm4::blit(sprite, x, y, width, height, flags);
+	}
+
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32)
+	{
+		wasm4::line(x1, y1, x2, y2);
+	}
+
+	fn hline(&mut self, x: i32, y: i32, len: u32)
+	{
+		wasm4::hline(x, y, len);
+	}
+
+	fn vline(&mut self, x: i32, y: i32, len: u32)
+	{
+		wasm4::vline(x, y, len);
+	}
+
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		wasm4::oval(x, y, width, height);
+	}
+
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		wasm4::rect(x, y, width, height);
+	}
+
+	fn text(&mut self, text: &str, x: i32, y: i32)
+	{
+		wasm4::text(text, x, y);
+	}
+
+	fn tone(&mut self, frequency: u32, duration: u32, volume: u32, flags: u32)
+	{
+		wasm4::tone(frequency, duration, volume, flags);
+	}
+
+	fn diskr(&mut self, buffer: &mut [u8]) -> usize
+	{
+		unsafe {
+			wasm4::diskr(buffer.as_mut_ptr(), buffer.len() as u32) as usize
+		}
+	}
+
+	fn diskw(&mut self, data: &[u8]) -> usize
+	{
+		unsafe { wasm4::diskw(data.as_ptr(), data.len() as u32) as usize }
+	}
+
+	fn trace(&mut self, text: &str)
+	{
+		wasm4::trace(text);
+	}
+}
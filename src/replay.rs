@@ -0,0 +1,481 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::checksum::Checksum;
+use crate::controls::{Movement, MAX_PLAYERS};
+use crate::difficulty::{Preset, MAX_LIVES};
+use crate::save::{Reader, Writer};
+
+/// Version 1 did not store the movement, which was always instant.
+const LEGACY_VERSION: u8 = 1;
+const LEGACY_HEADER_SIZE: usize = 10;
+/// Version 2 had only a single player.
+const SINGLE_PLAYER_VERSION: u8 = 2;
+const SINGLE_PLAYER_HEADER_SIZE: usize = 11;
+/// Version 3 did not store the lives, which were the preset's.
+const PRESET_LIVES_VERSION: u8 = 3;
+const PRESET_LIVES_HEADER_SIZE: usize = 12;
+const FORMAT_VERSION: u8 = 4;
+const HEADER_SIZE: usize = 13;
+
+/// A run is fully defined by the seed of its rng, its difficulty preset,
+/// movement, number of players and lives, plus the state of every player's
+/// gamepad on every frame, which is stored run-length encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recording
+{
+	pub seed: u64,
+	pub preset: Preset,
+	pub movement: Movement,
+	pub num_players: usize,
+	pub num_lives: i32,
+	runs: Vec<Run>,
+	/// Frames at the start whose gamepads were left out, so that a suspended
+	/// run fits on the disk. Such a run can be continued but not replayed.
+	num_skipped: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Run
+{
+	gamepads: [u8; MAX_PLAYERS],
+	num_frames: u16,
+}
+
+impl Recording
+{
+	pub fn new(
+		seed: u64,
+		preset: Preset,
+		movement: Movement,
+		num_players: usize,
+	) -> Self
+	{
+		Self {
+			seed,
+			preset,
+			movement,
+			num_players,
+			num_lives: preset.num_lives(),
+			runs: Vec::new(),
+			num_skipped: 0,
+		}
+	}
+
+	/// Only the gamepads of actual players are kept.
+	pub fn push(&mut self, mut gamepads: [u8; MAX_PLAYERS])
+	{
+		gamepads[self.num_players..].fill(0);
+		match self.runs.last_mut()
+		{
+			Some(run)
+				if run.gamepads == gamepads && run.num_frames < u16::MAX =>
+			{
+				run.num_frames += 1;
+			}
+			_ => self.runs.push(Run {
+				gamepads,
+				num_frames: 1,
+			}),
+		}
+	}
+
+	pub fn num_frames(&self) -> u32
+	{
+		let num_recorded: u32 =
+			self.runs.iter().map(|run| run.num_frames as u32).sum();
+		self.num_skipped + num_recorded
+	}
+
+	pub fn is_complete(&self) -> bool
+	{
+		self.num_skipped == 0
+	}
+
+	/// Drops every frame after the first `num_frames`.
+	pub fn truncate(&mut self, num_frames: u32)
+	{
+		if num_frames <= self.num_skipped
+		{
+			self.num_skipped = num_frames;
+			self.runs.clear();
+			return;
+		}
+		let mut num_left = num_frames - self.num_skipped;
+		let mut num_runs = 0;
+		for run in &mut self.runs
+		{
+			if num_left == 0
+			{
+				break;
+			}
+			let n = std::cmp::min(run.num_frames as u32, num_left);
+			run.num_frames = n as u16;
+			num_left -= n;
+			num_runs += 1;
+		}
+		self.runs.truncate(num_runs);
+	}
+
+	pub fn playback(&self) -> Playback
+	{
+		Playback {
+			recording: self.clone(),
+			run_index: 0,
+			frame_in_run: 0,
+		}
+	}
+
+	/// A format version, the seed, the preset, the movement, the number of
+	/// players, the lives and then a gamepad per player plus 2 bytes per run.
+	pub fn to_bytes(&self) -> Vec<u8>
+	{
+		self.encode(&self.runs)
+	}
+
+	fn encode(&self, runs: &[Run]) -> Vec<u8>
+	{
+		let run_size = self.num_players + 2;
+		let mut bytes = Vec::with_capacity(HEADER_SIZE + run_size * runs.len());
+		bytes.push(FORMAT_VERSION);
+		bytes.extend_from_slice(&self.seed.to_le_bytes());
+		bytes.push(self.preset.index() as u8);
+		bytes.push(self.movement.index() as u8);
+		bytes.push(self.num_players as u8);
+		bytes.push(self.num_lives as u8);
+		for run in runs
+		{
+			bytes.extend_from_slice(&run.gamepads[..self.num_players]);
+			bytes.extend_from_slice(&run.num_frames.to_le_bytes());
+		}
+		bytes
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self>
+	{
+		let header_size = match bytes.first()
+		{
+			Some(&LEGACY_VERSION) => LEGACY_HEADER_SIZE,
+			Some(&SINGLE_PLAYER_VERSION) => SINGLE_PLAYER_HEADER_SIZE,
+			Some(&PRESET_LIVES_VERSION) => PRESET_LIVES_HEADER_SIZE,
+			Some(&FORMAT_VERSION) => HEADER_SIZE,
+			_ => return None,
+		};
+		let header = bytes.get(..header_size)?;
+		let seed = u64::from_le_bytes(header[1..9].try_into().ok()?);
+		let preset = Preset::from_index(header[9] as usize)?;
+		let movement = match header[0]
+		{
+			LEGACY_VERSION => Movement::Instant,
+			_ => Movement::from_index(header[10] as usize)?,
+		};
+		let num_players = match header[0]
+		{
+			LEGACY_VERSION | SINGLE_PLAYER_VERSION => 1,
+			_ => header[11] as usize,
+		};
+		let num_lives = match header[0]
+		{
+			FORMAT_VERSION => header[12] as i32,
+			_ => preset.num_lives(),
+		};
+		if !(1..=MAX_PLAYERS).contains(&num_players)
+			|| !(1..=MAX_LIVES).contains(&num_lives)
+		{
+			return None;
+		}
+
+		let run_size = num_players + 2;
+		let body = &bytes[header_size..];
+		if !body.len().is_multiple_of(run_size)
+		{
+			return None;
+		}
+		let runs = body
+			.chunks(run_size)
+			.map(|chunk| {
+				let mut gamepads = [0; MAX_PLAYERS];
+				gamepads[..num_players].copy_from_slice(&chunk[..num_players]);
+				let num_frames = [chunk[num_players], chunk[num_players + 1]];
+				Run {
+					gamepads,
+					num_frames: u16::from_le_bytes(num_frames),
+				}
+			})
+			.collect();
+		Some(Self {
+			seed,
+			preset,
+			movement,
+			num_players,
+			num_lives,
+			runs,
+			num_skipped: 0,
+		})
+	}
+
+	/// The bytes of `to_bytes()`, preceded by their length and followed by
+	/// the number of frames that were skipped.
+	pub fn write(&self, writer: &mut Writer)
+	{
+		let bytes = self.to_bytes();
+		writer.u16(bytes.len() as u16);
+		writer.bytes(&bytes);
+		writer.u32(self.num_skipped);
+	}
+
+	/// Like `write()`, but skips every frame so far.
+	pub fn write_without_frames(&self, writer: &mut Writer)
+	{
+		let bytes = self.encode(&[]);
+		writer.u16(bytes.len() as u16);
+		writer.bytes(&bytes);
+		writer.u32(self.num_frames());
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let len = reader.u16()? as usize;
+		let recording = Self::from_bytes(reader.bytes(len)?)?;
+		Some(Self {
+			num_skipped: reader.u32()?,
+			..recording
+		})
+	}
+
+	/// A shareable text version of `to_bytes()`, for `trace`.
+	pub fn to_hex(&self) -> String
+	{
+		self.to_bytes()
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect()
+	}
+
+	pub fn from_hex(text: &str) -> Option<Self>
+	{
+		let digits: Vec<u8> = text
+			.chars()
+			.filter(|c| !c.is_whitespace())
+			.map(|c| c.to_digit(16).map(|digit| digit as u8))
+			.collect::<Option<_>>()?;
+		if !digits.len().is_multiple_of(2)
+		{
+			return None;
+		}
+		let bytes: Vec<u8> = digits
+			.chunks(2)
+			.map(|pair| (pair[0] << 4) | pair[1])
+			.collect();
+		Self::from_bytes(&bytes)
+	}
+}
+
+/// Substitutes a recorded gamepad stream for live input.
+pub struct Playback
+{
+	recording: Recording,
+	run_index: usize,
+	frame_in_run: u16,
+}
+
+impl Playback
+{
+	pub fn is_finished(&self) -> bool
+	{
+		self.run_index >= self.recording.runs.len()
+	}
+
+	/// The gamepad states for the next frame, if the recording has one.
+	pub fn next_gamepads(&mut self) -> Option<[u8; MAX_PLAYERS]>
+	{
+		let run = self.recording.runs.get(self.run_index)?;
+		self.frame_in_run += 1;
+		if self.frame_in_run >= run.num_frames
+		{
+			self.run_index += 1;
+			self.frame_in_run = 0;
+		}
+		Some(run.gamepads)
+	}
+
+	pub fn hash_into(&self, checksum: &mut Checksum)
+	{
+		checksum.u64(self.recording.seed);
+		checksum.u64(self.recording.num_frames() as u64);
+		checksum.i32(self.run_index as i32);
+		checksum.i32(self.frame_in_run as i32);
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		self.recording.write(writer);
+		writer.u16(self.run_index as u16);
+		writer.u16(self.frame_in_run);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let recording = Recording::read(reader)?;
+		if !recording.is_complete()
+		{
+			return None;
+		}
+		let run_index = reader.u16()? as usize;
+		let frame_in_run = reader.u16()?;
+		let num_frames = match recording.runs.get(run_index)
+		{
+			Some(run) => run.num_frames,
+			None if run_index == recording.runs.len() => 1,
+			None => return None,
+		};
+		if frame_in_run >= num_frames
+		{
+			return None;
+		}
+		Some(Self {
+			recording,
+			run_index,
+			frame_in_run,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_run_length_encoding()
+	{
+		let input = [0, 0, 0, 16, 16, 1, 0, 0];
+		let mut recording =
+			Recording::new(1234, Preset::Normal, Movement::Instant, 1);
+		for &gamepad in &input
+		{
+			recording.push([gamepad, 0, 0, 0]);
+		}
+		assert_eq!(recording.runs.len(), 4);
+		assert_eq!(recording.num_frames(), input.len() as u32);
+
+		let mut playback = recording.playback();
+		for &gamepad in &input
+		{
+			assert_eq!(playback.next_gamepads(), Some([gamepad, 0, 0, 0]));
+		}
+		assert_eq!(playback.next_gamepads(), None);
+		assert!(playback.is_finished());
+
+		recording.truncate(4);
+		assert_eq!(recording.runs.len(), 2);
+		assert_eq!(recording.num_frames(), 4);
+		recording.truncate(10);
+		assert_eq!(recording.num_frames(), 4);
+		recording.truncate(0);
+		assert!(recording.runs.is_empty());
+	}
+
+	#[test]
+	fn test_round_trip()
+	{
+		let mut recording = Recording::new(
+			0x0123456789abcdef,
+			Preset::Insane,
+			Movement::Momentum,
+			3,
+		);
+		for i in 0..1000
+		{
+			let gamepad = (i / 7) as u8 & 0xf0;
+			recording.push([gamepad, gamepad >> 4, (i / 50) as u8, 0xff]);
+		}
+		recording.num_lives = 2;
+		let bytes = recording.to_bytes();
+		assert_eq!(bytes[HEADER_SIZE - 2], 3);
+		assert_eq!(bytes[HEADER_SIZE - 1], 2);
+		assert_eq!(Recording::from_bytes(&bytes), Some(recording.clone()));
+		let hex = recording.to_hex();
+		assert_eq!(Recording::from_hex(&hex), Some(recording));
+		assert_eq!(Recording::from_bytes(&bytes[..11]), None);
+		assert_eq!(Recording::from_bytes(&bytes[..(bytes.len() - 1)]), None);
+		assert_eq!(Recording::from_hex("xyz"), None);
+	}
+
+	#[test]
+	fn test_frames_can_be_skipped()
+	{
+		let mut recording =
+			Recording::new(1234, Preset::Normal, Movement::Instant, 2);
+		for i in 0..1000
+		{
+			recording.push([(i / 3) as u8, (i / 5) as u8, 0, 0]);
+		}
+		let mut writer = Writer::new();
+		recording.write_without_frames(&mut writer);
+		let bytes = writer.into_bytes();
+		assert_eq!(bytes.len(), 2 + HEADER_SIZE + 4);
+		let mut skipped = Recording::read(&mut Reader::new(&bytes)).unwrap();
+		assert!(!skipped.is_complete());
+		assert_eq!(skipped.num_frames(), 1000);
+		assert_eq!(skipped.playback().next_gamepads(), None);
+
+		// New frames are recorded after the skipped ones.
+		skipped.push([1, 2, 0, 0]);
+		assert_eq!(skipped.num_frames(), 1001);
+		let mut writer = Writer::new();
+		skipped.write(&mut writer);
+		let bytes = writer.into_bytes();
+		let mut reader = Reader::new(&bytes);
+		assert_eq!(Recording::read(&mut reader), Some(skipped.clone()));
+		assert!(reader.is_empty());
+
+		skipped.truncate(1001);
+		assert_eq!(skipped.num_frames(), 1001);
+		skipped.truncate(600);
+		assert_eq!(skipped.num_frames(), 600);
+		assert!(skipped.runs.is_empty());
+	}
+
+	#[test]
+	fn test_legacy_recordings_use_instant_movement()
+	{
+		let recording =
+			Recording::from_hex("011f0000000000000001 003c00").unwrap();
+		assert_eq!(recording.seed, 0x1f);
+		assert_eq!(recording.preset, Preset::Normal);
+		assert_eq!(recording.movement, Movement::Instant);
+		assert_eq!(recording.num_players, 1);
+		assert_eq!(recording.num_frames(), 60);
+	}
+
+	#[test]
+	fn test_single_player_recordings_are_still_read()
+	{
+		let recording =
+			Recording::from_hex("021f000000000000000201 043c00").unwrap();
+		assert_eq!(recording.preset, Preset::Hard);
+		assert_eq!(recording.movement, Movement::Momentum);
+		assert_eq!(recording.num_players, 1);
+		let mut playback = recording.playback();
+		assert_eq!(playback.next_gamepads(), Some([4, 0, 0, 0]));
+	}
+
+	#[test]
+	fn test_recordings_without_lives_use_the_preset()
+	{
+		let recording =
+			Recording::from_hex("031f00000000000000000102 00043c00").unwrap();
+		assert_eq!(recording.preset, Preset::Easy);
+		assert_eq!(recording.num_players, 2);
+		assert_eq!(recording.num_lives, Preset::Easy.num_lives());
+		let mut playback = recording.playback();
+		assert_eq!(playback.next_gamepads(), Some([0, 4, 0, 0]));
+		// Every run has at least one life.
+		let no_lives = "041f0000000000000001000100 003c00";
+		assert_eq!(Recording::from_hex(no_lives), None);
+	}
+}
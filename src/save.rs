@@ -0,0 +1,613 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Save data lives on the WASM-4 disk, which holds at most 1024 bytes. It
+//! starts with a header made of a magic number, the format version, the size
+//! of the payload and a checksum of the payload. Older versions are migrated
+//! when they are loaded; anything unrecognizable is replaced by defaults.
+
+use crate::difficulty::{Preset, NUM_PRESETS};
+use crate::fixed::{Fixed, Vector};
+use crate::highscores::HighScores;
+use crate::platform::Platform;
+use crate::replay::Recording;
+
+pub const DISK_SIZE: usize = 1024;
+
+const MAGIC: [u8; 4] = *b"DGBL";
+const HEADER_SIZE: usize = 11;
+
+/// Version 1 was a bare high score table without a header.
+const LEGACY_VERSION: u8 = 1;
+/// Version 2 added the header but still had only a single table.
+/// Version 3 has a high score table for each difficulty preset.
+/// Version 4 added a single saved replay.
+/// Version 5 added a snapshot of a suspended game.
+const CURRENT_VERSION: u8 = 5;
+
+pub struct SaveData
+{
+	pub high_scores: [HighScores; NUM_PRESETS],
+	pub replay: Option<Recording>,
+	/// A game that was suspended mid-run, to be resumed on the next start.
+	pub suspended: Option<Vec<u8>>,
+}
+
+impl SaveData
+{
+	pub const fn new() -> Self
+	{
+		const EMPTY: HighScores = HighScores::new();
+		Self {
+			high_scores: [EMPTY; NUM_PRESETS],
+			replay: None,
+			suspended: None,
+		}
+	}
+
+	pub fn load(platform: &mut dyn Platform) -> Self
+	{
+		let mut buffer = [0u8; DISK_SIZE];
+		let len = platform.diskr(&mut buffer);
+		Self::from_bytes(&buffer[..len]).unwrap_or_else(Self::new)
+	}
+
+	pub fn store(&self, platform: &mut dyn Platform)
+	{
+		platform.diskw(&self.to_bytes());
+	}
+
+	/// Keeps the recording, overwriting the previous one, unless it does not
+	/// fit on the disk.
+	pub fn store_replay(
+		&mut self,
+		platform: &mut dyn Platform,
+		recording: &Recording,
+	) -> bool
+	{
+		let previous = self.replay.replace(recording.clone());
+		if self.to_bytes().len() > DISK_SIZE
+		{
+			self.replay = previous;
+			return false;
+		}
+		self.store(platform);
+		true
+	}
+
+	/// Keeps the snapshot unless it does not fit on the disk next to
+	/// everything else.
+	pub fn store_suspended(
+		&mut self,
+		platform: &mut dyn Platform,
+		snapshot: Vec<u8>,
+	) -> bool
+	{
+		let previous = self.suspended.replace(snapshot);
+		if self.to_bytes().len() > DISK_SIZE
+		{
+			self.suspended = previous;
+			return false;
+		}
+		self.store(platform);
+		true
+	}
+
+	fn to_bytes(&self) -> Vec<u8>
+	{
+		let mut payload = Writer::new();
+		for table in &self.high_scores
+		{
+			table.write(&mut payload);
+		}
+		let replay = self.replay.as_ref().map(Recording::to_bytes);
+		let replay = replay.unwrap_or_default();
+		payload.u16(replay.len() as u16);
+		payload.bytes(&replay);
+		let suspended = self.suspended.as_deref().unwrap_or_default();
+		payload.u16(suspended.len() as u16);
+		payload.bytes(suspended);
+		with_header(CURRENT_VERSION, &payload.into_bytes())
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Option<Self>
+	{
+		if !bytes.starts_with(&MAGIC)
+		{
+			return match bytes.first()
+			{
+				Some(&LEGACY_VERSION) => Self::read_v1(&mut Reader::new(bytes)),
+				_ => None,
+			};
+		}
+
+		let mut header = Reader::new(bytes.get(..HEADER_SIZE)?);
+		header.skip(MAGIC.len())?;
+		let version = header.u8()?;
+		let len = header.u16()? as usize;
+		let checksum = header.u32()?;
+		let payload = bytes.get(HEADER_SIZE..(HEADER_SIZE + len))?;
+		if fnv1a(payload) != checksum
+		{
+			return None;
+		}
+
+		let mut reader = Reader::new(payload);
+		match version
+		{
+			2 => Self::read_v2(&mut reader),
+			3 => Self::read_v3(&mut reader),
+			4 => Self::read_v4(&mut reader),
+			5 => Self::read_v5(&mut reader),
+			_ => None,
+		}
+	}
+
+	fn read_v1(reader: &mut Reader) -> Option<Self>
+	{
+		reader.u8()?;
+		Self::read_v2(reader)
+	}
+
+	fn read_v2(reader: &mut Reader) -> Option<Self>
+	{
+		let mut save_data = Self::new();
+		save_data.high_scores[Preset::Normal.index()] =
+			HighScores::read(reader)?;
+		Some(save_data)
+	}
+
+	fn read_v3(reader: &mut Reader) -> Option<Self>
+	{
+		let mut save_data = Self::new();
+		for table in &mut save_data.high_scores
+		{
+			*table = HighScores::read(reader)?;
+		}
+		Some(save_data)
+	}
+
+	fn read_v4(reader: &mut Reader) -> Option<Self>
+	{
+		let mut save_data = Self::read_v3(reader)?;
+		let len = reader.u16()? as usize;
+		if len > 0
+		{
+			let bytes = reader.bytes(len)?;
+			save_data.replay = Some(Recording::from_bytes(bytes)?);
+		}
+		Some(save_data)
+	}
+
+	fn read_v5(reader: &mut Reader) -> Option<Self>
+	{
+		let mut save_data = Self::read_v4(reader)?;
+		let len = reader.u16()? as usize;
+		if len > 0
+		{
+			save_data.suspended = Some(reader.bytes(len)?.to_vec());
+		}
+		Some(save_data)
+	}
+}
+
+fn with_header(version: u8, payload: &[u8]) -> Vec<u8>
+{
+	let mut writer = Writer::new();
+	for byte in MAGIC
+	{
+		writer.u8(byte);
+	}
+	writer.u8(version);
+	writer.u16(payload.len() as u16);
+	writer.u32(fnv1a(payload));
+	let mut bytes = writer.into_bytes();
+	bytes.extend_from_slice(payload);
+	bytes
+}
+
+pub struct Writer
+{
+	bytes: Vec<u8>,
+}
+
+impl Writer
+{
+	pub fn new() -> Self
+	{
+		Self { bytes: Vec::new() }
+	}
+
+	pub fn into_bytes(self) -> Vec<u8>
+	{
+		self.bytes
+	}
+
+	pub fn u8(&mut self, value: u8)
+	{
+		self.bytes.push(value);
+	}
+
+	pub fn u16(&mut self, value: u16)
+	{
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub fn u32(&mut self, value: u32)
+	{
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub fn i32(&mut self, value: i32)
+	{
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub fn u64(&mut self, value: u64)
+	{
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub fn bytes(&mut self, bytes: &[u8])
+	{
+		self.bytes.extend_from_slice(bytes);
+	}
+
+	pub fn bool(&mut self, value: bool)
+	{
+		self.u8(value as u8);
+	}
+
+	/// Takes one byte for every 7 bits, after interleaving negative and
+	/// positive values, so that small numbers take up a single byte.
+	pub fn varint(&mut self, value: i32)
+	{
+		let mut bits = ((value << 1) ^ (value >> 31)) as u32;
+		while bits >= 0x80
+		{
+			self.u8((bits as u8) | 0x80);
+			bits >>= 7;
+		}
+		self.u8(bits as u8);
+	}
+
+	pub fn fixed(&mut self, value: Fixed)
+	{
+		self.varint(value.to_bits());
+	}
+
+	pub fn vector(&mut self, value: Vector)
+	{
+		self.fixed(value.x);
+		self.fixed(value.y);
+	}
+
+	/// A flag for whether there is a value, followed by the value itself.
+	pub fn option<T>(
+		&mut self,
+		value: Option<T>,
+		write: impl FnOnce(&mut Self, T),
+	)
+	{
+		self.bool(value.is_some());
+		if let Some(value) = value
+		{
+			write(self, value);
+		}
+	}
+}
+
+pub struct Reader<'a>
+{
+	bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a>
+{
+	pub fn new(bytes: &'a [u8]) -> Self
+	{
+		Self { bytes }
+	}
+
+	fn take<const N: usize>(&mut self) -> Option<[u8; N]>
+	{
+		self.bytes(N)?.try_into().ok()
+	}
+
+	pub fn skip(&mut self, n: usize) -> Option<()>
+	{
+		self.bytes(n).map(|_| ())
+	}
+
+	pub fn bytes(&mut self, n: usize) -> Option<&'a [u8]>
+	{
+		let (head, rest) = self.bytes.split_at_checked(n)?;
+		self.bytes = rest;
+		Some(head)
+	}
+
+	pub fn u8(&mut self) -> Option<u8>
+	{
+		self.take::<1>().map(|bytes| bytes[0])
+	}
+
+	pub fn u16(&mut self) -> Option<u16>
+	{
+		self.take().map(u16::from_le_bytes)
+	}
+
+	pub fn u32(&mut self) -> Option<u32>
+	{
+		self.take().map(u32::from_le_bytes)
+	}
+
+	pub fn i32(&mut self) -> Option<i32>
+	{
+		self.take().map(i32::from_le_bytes)
+	}
+
+	pub fn u64(&mut self) -> Option<u64>
+	{
+		self.take().map(u64::from_le_bytes)
+	}
+
+	pub fn bool(&mut self) -> Option<bool>
+	{
+		match self.u8()?
+		{
+			0 => Some(false),
+			1 => Some(true),
+			_ => None,
+		}
+	}
+
+	pub fn varint(&mut self) -> Option<i32>
+	{
+		let mut bits: u32 = 0;
+		for shift in (0..32).step_by(7)
+		{
+			let byte = self.u8()?;
+			bits |= ((byte & 0x7f) as u32) << shift;
+			if byte & 0x80 == 0
+			{
+				return Some(((bits >> 1) as i32) ^ -((bits & 1) as i32));
+			}
+		}
+		None
+	}
+
+	pub fn fixed(&mut self) -> Option<Fixed>
+	{
+		self.varint().map(Fixed::from_bits)
+	}
+
+	pub fn vector(&mut self) -> Option<Vector>
+	{
+		Some(Vector::new(self.fixed()?, self.fixed()?))
+	}
+
+	pub fn option<T>(
+		&mut self,
+		read: impl FnOnce(&mut Self) -> Option<T>,
+	) -> Option<Option<T>>
+	{
+		if self.bool()?
+		{
+			read(self).map(Some)
+		}
+		else
+		{
+			Some(None)
+		}
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		self.bytes.is_empty()
+	}
+}
+
+/// The 32-bit FNV-1a hash.
+pub fn fnv1a(bytes: &[u8]) -> u32
+{
+	let mut hash: u32 = 0x811c9dc5;
+	for &byte in bytes
+	{
+		hash ^= byte as u32;
+		hash = hash.wrapping_mul(0x01000193);
+	}
+	hash
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::controls::Movement;
+	use crate::wasm4::BUTTON_LEFT;
+
+	fn example() -> SaveData
+	{
+		let mut save_data = SaveData::new();
+		for (t, table) in save_data.high_scores.iter_mut().enumerate()
+		{
+			for i in 0..(4 + 2 * t as i32)
+			{
+				table.submit(i * 7 % 5, i * 100, (t as u64) << 8 | i as u64);
+			}
+		}
+		save_data
+	}
+
+	fn normal_only(save_data: &SaveData) -> SaveData
+	{
+		let mut migrated = SaveData::new();
+		let table = &save_data.high_scores[Preset::Normal.index()];
+		let mut writer = Writer::new();
+		table.write(&mut writer);
+		let bytes = writer.into_bytes();
+		migrated.high_scores[Preset::Normal.index()] =
+			HighScores::read(&mut Reader::new(&bytes)).unwrap();
+		migrated
+	}
+
+	fn write_v1(save_data: &SaveData) -> Vec<u8>
+	{
+		let mut writer = Writer::new();
+		writer.u8(LEGACY_VERSION);
+		save_data.high_scores[Preset::Normal.index()].write(&mut writer);
+		writer.into_bytes()
+	}
+
+	fn write_v2(save_data: &SaveData) -> Vec<u8>
+	{
+		let mut writer = Writer::new();
+		save_data.high_scores[Preset::Normal.index()].write(&mut writer);
+		with_header(2, &writer.into_bytes())
+	}
+
+	fn write_v3(save_data: &SaveData) -> Vec<u8>
+	{
+		let mut writer = Writer::new();
+		for table in &save_data.high_scores
+		{
+			table.write(&mut writer);
+		}
+		with_header(3, &writer.into_bytes())
+	}
+
+	fn write_v4(save_data: &SaveData) -> Vec<u8>
+	{
+		let mut writer = Writer::new();
+		for table in &save_data.high_scores
+		{
+			table.write(&mut writer);
+		}
+		let replay = save_data.replay.as_ref().unwrap().to_bytes();
+		writer.u16(replay.len() as u16);
+		writer.bytes(&replay);
+		with_header(4, &writer.into_bytes())
+	}
+
+	#[test]
+	fn test_round_trip_v1()
+	{
+		let save_data = example();
+		let loaded = SaveData::from_bytes(&write_v1(&save_data)).unwrap();
+		assert_eq!(loaded.high_scores, normal_only(&save_data).high_scores);
+		// Loading migrates to the current version.
+		assert_eq!(loaded.to_bytes(), normal_only(&save_data).to_bytes());
+	}
+
+	#[test]
+	fn test_round_trip_v2()
+	{
+		let save_data = example();
+		let loaded = SaveData::from_bytes(&write_v2(&save_data)).unwrap();
+		assert_eq!(loaded.high_scores, normal_only(&save_data).high_scores);
+		assert_eq!(loaded.to_bytes(), normal_only(&save_data).to_bytes());
+	}
+
+	#[test]
+	fn test_round_trip_v3()
+	{
+		let save_data = example();
+		let loaded = SaveData::from_bytes(&write_v3(&save_data)).unwrap();
+		assert_eq!(loaded.high_scores, save_data.high_scores);
+		assert_eq!(loaded.replay, None);
+		assert_eq!(loaded.to_bytes(), save_data.to_bytes());
+	}
+
+	#[test]
+	fn test_round_trip_v4()
+	{
+		let mut save_data = example();
+		let mut recording =
+			Recording::new(99, Preset::Hard, Movement::Instant, 1);
+		for i in 0..200
+		{
+			recording.push([(i / 7) as u8, 0, 0, 0]);
+		}
+		save_data.replay = Some(recording);
+		let bytes = write_v4(&save_data);
+		assert!(bytes.len() <= DISK_SIZE);
+		assert_eq!(bytes[MAGIC.len()], 4);
+		let loaded = SaveData::from_bytes(&bytes).unwrap();
+		assert_eq!(loaded.high_scores, save_data.high_scores);
+		assert_eq!(loaded.replay, save_data.replay);
+		assert_eq!(loaded.suspended, None);
+		assert_eq!(loaded.to_bytes(), save_data.to_bytes());
+	}
+
+	#[test]
+	fn test_round_trip_v5()
+	{
+		let mut save_data = example();
+		save_data.suspended = Some((0..=255).collect());
+		let bytes = save_data.to_bytes();
+		assert_eq!(bytes[MAGIC.len()], 5);
+		let loaded = SaveData::from_bytes(&bytes).unwrap();
+		assert_eq!(loaded.suspended, save_data.suspended);
+		assert_eq!(loaded.to_bytes(), bytes);
+
+		let mut console = crate::headless::Headless::new();
+		assert!(!save_data.store_suspended(&mut console, vec![0; DISK_SIZE]));
+		assert_eq!(save_data.suspended.as_ref().unwrap().len(), 256);
+	}
+
+	#[test]
+	fn test_varints()
+	{
+		let values = [0, 1, -1, 63, -64, 64, 300, -20480, i32::MAX, i32::MIN];
+		let mut writer = Writer::new();
+		for value in values
+		{
+			writer.varint(value);
+		}
+		let bytes = writer.into_bytes();
+		assert_eq!(bytes[..5], [0, 2, 1, 0x7e, 0x7f]);
+		let mut reader = Reader::new(&bytes);
+		for value in values
+		{
+			assert_eq!(reader.varint(), Some(value));
+		}
+		assert!(reader.is_empty());
+		assert_eq!(Reader::new(&[0x80; 6]).varint(), None);
+	}
+
+	#[test]
+	fn test_replays_that_do_not_fit_are_refused()
+	{
+		let mut console = crate::headless::Headless::new();
+		let mut save_data = example();
+		let mut recording =
+			Recording::new(5, Preset::Easy, Movement::Instant, 1);
+		recording.push([BUTTON_LEFT, 0, 0, 0]);
+		assert!(save_data.store_replay(&mut console, &recording));
+		for i in 0..DISK_SIZE
+		{
+			recording.push([(i % 2) as u8, 0, 0, 0]);
+		}
+		assert!(!save_data.store_replay(&mut console, &recording));
+		assert_eq!(save_data.replay.as_ref().unwrap().num_frames(), 1);
+	}
+
+	#[test]
+	fn test_corruption_is_detected()
+	{
+		let bytes = example().to_bytes();
+		for i in 0..bytes.len()
+		{
+			let mut corrupted = bytes.clone();
+			corrupted[i] ^= 0x10;
+			assert!(SaveData::from_bytes(&corrupted).is_none());
+		}
+		assert!(SaveData::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+		assert!(SaveData::from_bytes(&[]).is_none());
+		assert!(SaveData::from_bytes(&[0u8; DISK_SIZE]).is_none());
+		assert!(SaveData::from_bytes(&[0xffu8; DISK_SIZE]).is_none());
+	}
+}
@@ -0,0 +1,152 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A seed code is six characters that pin down both the difficulty preset
+//! and the seed of a run, so that players can share a ball sequence. Each
+//! character holds 5 bits: the first 2 bits are the preset and the other 28
+//! are the seed, scrambled so that nearby seeds get unrelated codes.
+
+use crate::difficulty::Preset;
+use crate::save::{Reader, Writer};
+
+pub const CODE_LENGTH: usize = 6;
+
+/// Leaves out I, O, 0 and 1, which are easily confused.
+const ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+const SEED_BITS: u32 = 28;
+const SEED_MASK: u64 = (1 << SEED_BITS) - 1;
+/// Multiplying by an odd number is a bijection modulo a power of two.
+const SCRAMBLE: u64 = 0xdeece6d;
+const UNSCRAMBLE: u64 = 0xde56b65;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedCode
+{
+	/// Indices into the alphabet.
+	digits: [u8; CODE_LENGTH],
+}
+
+impl SeedCode
+{
+	/// The code made of only the first character of the alphabet.
+	pub const FIRST: SeedCode = SeedCode {
+		digits: [0; CODE_LENGTH],
+	};
+
+	/// Seeds outside of the 28 bits that fit in a code have no code.
+	pub fn new(preset: Preset, seed: u64) -> Option<Self>
+	{
+		if seed > SEED_MASK
+		{
+			return None;
+		}
+		let scrambled = (seed * SCRAMBLE) & SEED_MASK;
+		let value = ((preset.index() as u64) << SEED_BITS) | scrambled;
+		let mut digits = [0; CODE_LENGTH];
+		for (i, digit) in digits.iter_mut().enumerate()
+		{
+			let shift = 5 * (CODE_LENGTH - 1 - i);
+			*digit = ((value >> shift) & 0x1f) as u8;
+		}
+		Some(Self { digits })
+	}
+
+	pub fn preset(&self) -> Preset
+	{
+		let index = (self.value() >> SEED_BITS) as usize;
+		Preset::from_index(index).unwrap_or(Preset::Normal)
+	}
+
+	pub fn seed(&self) -> u64
+	{
+		((self.value() & SEED_MASK) * UNSCRAMBLE) & SEED_MASK
+	}
+
+	fn value(&self) -> u64
+	{
+		self.digits
+			.iter()
+			.fold(0, |value, &digit| (value << 5) | digit as u64)
+	}
+
+	/// Steps a single character forwards or backwards through the alphabet.
+	pub fn cycle(&mut self, position: usize, step: i32)
+	{
+		let digit = &mut self.digits[position];
+		*digit = (*digit as i32 + step).rem_euclid(ALPHABET.len() as i32) as u8;
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.bytes(&self.digits);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let digits: [u8; CODE_LENGTH] =
+			reader.bytes(CODE_LENGTH)?.try_into().ok()?;
+		if digits.iter().any(|&digit| digit as usize >= ALPHABET.len())
+		{
+			return None;
+		}
+		Some(Self { digits })
+	}
+
+	pub fn to_text(self) -> String
+	{
+		let bytes = self.digits.map(|digit| ALPHABET[digit as usize]);
+		String::from_utf8_lossy(&bytes).into_owned()
+	}
+}
+
+/// Cuts a seed down to the bits that fit in a code.
+pub fn truncate(seed: u64) -> u64
+{
+	seed & SEED_MASK
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_round_trip()
+	{
+		for preset in Preset::ALL
+		{
+			for seed in [0, 1, 2, 1234, SEED_MASK]
+			{
+				let code = SeedCode::new(preset, seed).unwrap();
+				assert_eq!(code.preset(), preset);
+				assert_eq!(code.seed(), seed);
+			}
+		}
+		assert_eq!(SeedCode::new(Preset::Easy, SEED_MASK + 1), None);
+	}
+
+	#[test]
+	fn test_nearby_seeds_look_different()
+	{
+		let a = SeedCode::new(Preset::Normal, 100).unwrap().to_text();
+		let b = SeedCode::new(Preset::Normal, 101).unwrap().to_text();
+		assert_eq!(a.len(), CODE_LENGTH);
+		assert!(a.bytes().zip(b.bytes()).filter(|(x, y)| x != y).count() > 2);
+	}
+
+	#[test]
+	fn test_every_code_is_valid()
+	{
+		let mut code = SeedCode::new(Preset::Easy, 0).unwrap();
+		for _ in 0..32
+		{
+			code.cycle(0, -1);
+			let seed = code.seed();
+			assert_eq!(SeedCode::new(code.preset(), seed), Some(code));
+		}
+	}
+}
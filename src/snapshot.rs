@@ -0,0 +1,429 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A snapshot is the complete state of the game in a compact buffer: the
+//! frame count, the settings and every scene on the stack, down to the
+//! internal state of each random number generator. Restoring one continues
+//! exactly where the game left off, which is how a game is suspended to the
+//! disk and how tests roll a game back to an earlier frame. The save data
+//! is left out, because it is on the disk already.
+
+use crate::challenge::Challenge;
+use crate::game_over::GameOver;
+use crate::leaderboard::Leaderboard;
+use crate::level::Level;
+use crate::mode_select::ModeSelect;
+use crate::options::{Options, Settings};
+use crate::pause::Pause;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::title::Title;
+use crate::versus::{Scoreboard, VersusSelect};
+use crate::{Game, Scene};
+
+/// Snapshots made by another version of the cart are not restored.
+const FORMAT_VERSION: u8 = 2;
+
+impl Game
+{
+	/// Nothing can be captured halfway through a fade, because the change
+	/// it is about to make cannot be stored.
+	pub fn snapshot(&self) -> Option<Vec<u8>>
+	{
+		self.write_snapshot(true)
+	}
+
+	/// Like `snapshot()`, but levels leave out the input they recorded,
+	/// which keeps long runs small enough to suspend to the disk at the
+	/// cost of their replay.
+	pub fn snapshot_without_recordings(&self) -> Option<Vec<u8>>
+	{
+		self.write_snapshot(false)
+	}
+
+	/// Long runs only fit on the disk without their replay.
+	pub fn store_suspended(&mut self, platform: &mut dyn Platform) -> bool
+	{
+		for snapshot in [self.snapshot(), self.snapshot_without_recordings()]
+		{
+			let save_data = &mut self.context.save_data;
+			if snapshot.is_some_and(|x| save_data.store_suspended(platform, x))
+			{
+				return true;
+			}
+		}
+		false
+	}
+
+	fn write_snapshot(&self, with_recordings: bool) -> Option<Vec<u8>>
+	{
+		if self.fade.is_some()
+		{
+			return None;
+		}
+		let mut writer = Writer::new();
+		writer.u8(FORMAT_VERSION);
+		writer.u64(self.context.ticks);
+		self.context.settings.write(&mut writer);
+		writer.u8(self.previous_gamepad);
+		writer.u8(self.scenes.len() as u8);
+		for scene in &self.scenes
+		{
+			scene.write(&mut writer, with_recordings);
+		}
+		Some(writer.into_bytes())
+	}
+
+	/// Leaves the game as it is unless the entire snapshot is valid.
+	pub fn restore(&mut self, snapshot: &[u8]) -> Option<()>
+	{
+		let mut reader = Reader::new(snapshot);
+		if reader.u8()? != FORMAT_VERSION
+		{
+			return None;
+		}
+		let ticks = reader.u64()?;
+		let settings = Settings::read(&mut reader)?;
+		let previous_gamepad = reader.u8()?;
+		let num_scenes = reader.u8()? as usize;
+		let mut scenes: Vec<Scene> = Vec::with_capacity(num_scenes);
+		for _ in 0..num_scenes
+		{
+			let scene = Scene::read(&mut reader, scenes.last())?;
+			scenes.push(scene);
+		}
+		if scenes.is_empty() || !reader.is_empty()
+		{
+			return None;
+		}
+
+		self.scenes = scenes;
+		self.fade = None;
+		self.context.ticks = ticks;
+		self.context.settings = settings;
+		self.previous_gamepad = previous_gamepad;
+		Some(())
+	}
+}
+
+impl Scene
+{
+	fn write(&self, writer: &mut Writer, with_recordings: bool)
+	{
+		match self
+		{
+			Scene::Title(title) =>
+			{
+				writer.u8(0);
+				title.write(writer);
+			}
+			Scene::ModeSelect(mode_select) =>
+			{
+				writer.u8(1);
+				mode_select.write(writer);
+			}
+			Scene::Challenge(challenge) =>
+			{
+				writer.u8(2);
+				challenge.write(writer);
+			}
+			Scene::Level(level) =>
+			{
+				writer.u8(3);
+				if with_recordings
+				{
+					level.write(writer);
+				}
+				else
+				{
+					level.write_without_recording(writer);
+				}
+			}
+			Scene::Pause(pause) =>
+			{
+				writer.u8(4);
+				pause.write(writer);
+			}
+			Scene::GameOver(game_over) =>
+			{
+				writer.u8(5);
+				game_over.write(writer);
+			}
+			Scene::HighScores(leaderboard) =>
+			{
+				writer.u8(6);
+				leaderboard.write(writer);
+			}
+			Scene::Options(options) =>
+			{
+				writer.u8(7);
+				options.write(writer);
+			}
+			Scene::VersusSelect(_) => writer.u8(8),
+			Scene::Scoreboard(scoreboard) =>
+			{
+				writer.u8(9);
+				scoreboard.write(writer);
+			}
+		}
+	}
+
+	/// An overlay is restored on top of the scene below it.
+	fn read(reader: &mut Reader, below: Option<&Scene>) -> Option<Self>
+	{
+		let scene = match reader.u8()?
+		{
+			0 => Scene::Title(Title::read(reader)?),
+			1 => Scene::ModeSelect(ModeSelect::read(reader)?),
+			2 => Scene::Challenge(Challenge::read(reader)?),
+			3 => Scene::Level(Box::new(Level::read(reader)?)),
+			4 =>
+			{
+				let Some(Scene::Level(level)) = below
+				else
+				{
+					return None;
+				};
+				Scene::Pause(Pause::read(reader, level.can_rewind())?)
+			}
+			5 => Scene::GameOver(Box::new(GameOver::read(reader)?)),
+			6 => Scene::HighScores(Leaderboard::read(reader)?),
+			7 => Scene::Options(Options::read(reader)?),
+			8 => Scene::VersusSelect(VersusSelect::new()),
+			9 => Scene::Scoreboard(Scoreboard::read(reader)?),
+			_ => return None,
+		};
+		Some(scene)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::controls::Movement;
+	use crate::difficulty::Preset;
+	use crate::headless::Headless;
+	use crate::replay::Recording;
+	use crate::save::{SaveData, DISK_SIZE};
+	use crate::test_support::{
+		pause, press, random_gamepad, start_run, wait_for_fade, wander,
+	};
+	use crate::wasm4::*;
+
+	/// Plays random frames and returns the checksum after each one.
+	fn play(game: &mut Game, console: &mut Headless, seed: u64) -> Vec<u64>
+	{
+		let rng = fastrand::Rng::with_seed(seed);
+		(0..600)
+			.map(|_| {
+				console.gamepads[0] = random_gamepad(&rng);
+				game.update(console);
+				game.checksum()
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_restored_game_continues_identically()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		start_run(&mut game, &mut console);
+		play(&mut game, &mut console, 1);
+		let snapshot = game.snapshot().unwrap();
+		let checksum = game.checksum();
+		// The save data is not part of the snapshot, so keep a copy on the
+		// disk to undo the high scores set by the first attempt.
+		let mut disk = Headless::new();
+		game.context.save_data.store(&mut disk);
+		let expected = play(&mut game, &mut console, 2);
+
+		// Rolling back the same game.
+		game.context.save_data = SaveData::load(&mut disk);
+		game.restore(&snapshot).unwrap();
+		assert_eq!(game.checksum(), checksum);
+		assert_eq!(play(&mut game, &mut console, 2), expected);
+
+		// Restoring into a game that was never started.
+		let mut restored = Game::new();
+		restored.context.save_data = SaveData::load(&mut disk);
+		restored.restore(&snapshot).unwrap();
+		assert_eq!(restored.snapshot().unwrap(), snapshot);
+		assert_eq!(play(&mut restored, &mut console, 2), expected);
+	}
+
+	#[test]
+	fn test_invalid_snapshots_are_refused()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		start_run(&mut game, &mut console);
+		play(&mut game, &mut console, 3);
+		let snapshot = game.snapshot().unwrap();
+		let checksum = game.checksum();
+		for len in 0..snapshot.len()
+		{
+			assert!(game.restore(&snapshot[..len]).is_none());
+		}
+		let mut extended = snapshot.clone();
+		extended.push(0);
+		assert!(game.restore(&extended).is_none());
+		assert_eq!(game.checksum(), checksum);
+
+		// Quitting from the pause menu fades out.
+		let mut game = Game::new();
+		start_run(&mut game, &mut console);
+		pause(&mut game, &mut console);
+		assert!(matches!(game.scenes.last(), Some(Scene::Pause(_))));
+		press(&mut game, &mut console, BUTTON_UP);
+		press(&mut game, &mut console, BUTTON_1);
+		assert!(game.fade.is_some());
+		assert!(game.snapshot().is_none());
+	}
+
+	fn level_checksum(game: &Game) -> u64
+	{
+		match &game.scenes[1]
+		{
+			Scene::Level(level) => level.checksum(),
+			_ => panic!("not a level"),
+		}
+	}
+
+	#[test]
+	fn test_games_that_do_not_fit_are_not_suspended()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		// Fill the disk with a saved replay that changes every frame.
+		let mut recording =
+			Recording::new(0, Preset::Normal, Movement::Instant, 1);
+		for frame in 0..1000
+		{
+			recording.push([(frame % 2) as u8 * BUTTON_LEFT, 0, 0, 0]);
+			let save_data = &mut game.context.save_data;
+			if !save_data.store_replay(&mut console, &recording)
+			{
+				break;
+			}
+		}
+		start_run(&mut game, &mut console);
+		play(&mut game, &mut console, 5);
+		pause(&mut game, &mut console);
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_1);
+		assert!(game.fade.is_none());
+		assert!(matches!(game.scenes.last(), Some(Scene::Pause(_))));
+		assert!(game.context.save_data.suspended.is_none());
+	}
+
+	#[test]
+	fn test_long_games_are_suspended_without_their_replay()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		start_run(&mut game, &mut console);
+		// Ten seconds of random input on every frame.
+		play(&mut game, &mut console, 5);
+		pause(&mut game, &mut console);
+		let mut expected = Game::new();
+		expected.restore(&game.snapshot().unwrap()).unwrap();
+		// The full snapshot does not fit.
+		assert!(game.snapshot().unwrap().len() > DISK_SIZE);
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_1);
+		assert!(game.fade.is_some());
+		assert!(game.context.save_data.suspended.is_some());
+
+		let mut resumed = Game::new();
+		resumed.start(&mut console);
+		assert_eq!(level_checksum(&resumed), level_checksum(&expected));
+		for game in [&mut resumed, &mut expected]
+		{
+			game.update(&mut console);
+			press(game, &mut console, BUTTON_1);
+		}
+		// Both continue identically until the run is over.
+		let rng = fastrand::Rng::with_seed(6);
+		while let [_, Scene::Level(level)] = &resumed.scenes[..]
+		{
+			assert_eq!(level.checksum(), level_checksum(&expected));
+			console.gamepads[0] = random_gamepad(&rng);
+			resumed.update(&mut console);
+			expected.update(&mut console);
+		}
+		assert!(matches!(resumed.scenes[..], [_, Scene::GameOver(_)]));
+		assert!(matches!(expected.scenes[..], [_, Scene::GameOver(_)]));
+
+		// The replay is gone, so it cannot be watched afterwards.
+		console.gamepads[0] = 0;
+		resumed.update(&mut console);
+		// Down past RETRY SEED and NEW SEED to WATCH REPLAY.
+		press(&mut resumed, &mut console, BUTTON_DOWN);
+		press(&mut resumed, &mut console, BUTTON_DOWN);
+		press(&mut resumed, &mut console, BUTTON_1);
+		assert!(matches!(resumed.scenes.last(), Some(Scene::GameOver(_))));
+	}
+
+	#[test]
+	fn test_suspended_game_resumes_once()
+	{
+		let mut console = Headless::new();
+		let mut game = Game::new();
+		start_run(&mut game, &mut console);
+		// Input that changes only every so often leaves a recording that
+		// is short enough to be suspended along with the run.
+		for frame in 0..300
+		{
+			console.gamepads[0] = wander(frame);
+			game.update(&mut console);
+		}
+		pause(&mut game, &mut console);
+		let checksum = level_checksum(&game);
+		// Down past RESTART to SUSPEND.
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_DOWN);
+		press(&mut game, &mut console, BUTTON_1);
+		wait_for_fade(&mut game, &mut console);
+		assert!(matches!(game.scenes[..], [Scene::Title(_)]));
+
+		let mut resumed = Game::new();
+		resumed.start(&mut console);
+		assert!(matches!(
+			resumed.scenes[..],
+			[Scene::Title(_), Scene::Level(_), Scene::Pause(_)]
+		));
+		assert_eq!(level_checksum(&resumed), checksum);
+		// The X that chose to suspend is still held in the snapshot.
+		resumed.update(&mut console);
+		press(&mut resumed, &mut console, BUTTON_1);
+		assert!(matches!(
+			resumed.scenes[..],
+			[Scene::Title(_), Scene::Level(_)]
+		));
+
+		let mut restarted = Game::new();
+		restarted.start(&mut console);
+		assert!(matches!(restarted.scenes[..], [Scene::Title(_)]));
+	}
+
+	#[test]
+	fn test_unreadable_suspended_game_is_kept()
+	{
+		let mut console = Headless::new();
+		let mut save_data = SaveData::new();
+		assert!(save_data.store_suspended(&mut console, vec![0xff]));
+		let mut game = Game::new();
+		game.start(&mut console);
+		assert!(matches!(game.scenes[..], [Scene::Title(_)]));
+		assert_eq!(console.traces.len(), 1);
+		let save_data = SaveData::load(&mut console);
+		assert_eq!(save_data.suspended, Some(vec![0xff]));
+	}
+}
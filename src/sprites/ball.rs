@@ -4,14 +4,24 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::collision::Hitbox;
+use crate::platform::Platform;
 
-pub fn draw(x: i32, y: i32)
+/// Balls hit whatever touches their shadow.
+pub const HITBOX: Hitbox = Hitbox::rect(-4, -1, 8, 3);
+/// Homing balls come in from any angle, so they are round.
+pub const HOMING_HITBOX: Hitbox = Hitbox::circle(0, 0, 3);
+
+pub const COLORS: u16 = 0x4320;
+/// A blue tint for balls that are going to bounce.
+pub const BOUNCING_COLORS: u16 = 0x4340;
+/// A cream tint for balls that are homing in on the little guy.
+pub const HOMING_COLORS: u16 = 0x4230;
+
+pub fn draw(platform: &mut dyn Platform, x: i32, y: i32, colors: u16)
 {
-	unsafe {
-		*DRAW_COLORS = 0x4320;
-	}
-	blit(
+	platform.set_draw_colors(colors);
+	platform.blit(
 		&BALL,
 		x - (BALL_WIDTH as i32) / 2,
 		y - (BALL_HEIGHT as i32) + 1,
@@ -21,8 +31,24 @@ pub fn draw(x: i32, y: i32)
 	);
 }
 
+/// Leaves out the shadow, for balls that are being carried overhead.
+pub fn draw_held(platform: &mut dyn Platform, x: i32, y: i32)
+{
+	platform.set_draw_colors(COLORS);
+	platform.blit(
+		&BALL,
+		x - (BALL_WIDTH as i32) / 2,
+		y - (BALL_HELD_HEIGHT as i32) + 1,
+		BALL_WIDTH,
+		BALL_HELD_HEIGHT,
+		BALL_FLAGS,
+	);
+}
+
 const BALL_WIDTH: u32 = 16;
 const BALL_HEIGHT: u32 = 16;
+/// The rows above the shadow.
+const BALL_HELD_HEIGHT: u32 = 10;
 const BALL_FLAGS: u32 = 1; // BLIT_2BPP
 const BALL: [u8; 64] = [
 	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x50, 0x00,
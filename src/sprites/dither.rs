@@ -0,0 +1,35 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::Platform;
+
+/// Dims an area by covering every other pixel of every other row.
+pub fn fill(platform: &mut dyn Platform, x: i32, y: i32, w: i32, h: i32)
+{
+	platform.set_draw_colors(0x40);
+	let size = DITHER_SIZE as i32;
+	// The pattern repeats every two pixels, so the last tile in each row and
+	// column can overlap its neighbour without breaking it up.
+	for ty in (0..h).step_by(DITHER_SIZE as usize)
+	{
+		for tx in (0..w).step_by(DITHER_SIZE as usize)
+		{
+			platform.blit(
+				&DITHER,
+				x + std::cmp::min(tx, w - size),
+				y + std::cmp::min(ty, h - size),
+				DITHER_SIZE,
+				DITHER_SIZE,
+				DITHER_FLAGS,
+			);
+		}
+	}
+}
+
+// dither
+const DITHER_SIZE: u32 = 8;
+const DITHER_FLAGS: u32 = 0; // BLIT_1BPP
+const DITHER: [u8; 8] = [0xaa, 0x00, 0xaa, 0x00, 0xaa, 0x00, 0xaa, 0x00];
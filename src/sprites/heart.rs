@@ -0,0 +1,19 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::Platform;
+
+pub fn draw(platform: &mut dyn Platform, x: i32, y: i32)
+{
+	platform.set_draw_colors(0x20);
+	platform.blit(&HEART, x, y, HEART_WIDTH, HEART_HEIGHT, HEART_FLAGS);
+}
+
+// heart
+const HEART_WIDTH: u32 = 8;
+const HEART_HEIGHT: u32 = 8;
+const HEART_FLAGS: u32 = 0; // BLIT_1BPP
+const HEART: [u8; 8] = [0x6c, 0xfe, 0xfe, 0xfe, 0x7c, 0x38, 0x10, 0x00];
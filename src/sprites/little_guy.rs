@@ -4,22 +4,43 @@
 // License: MIT
 //
 
+use crate::checksum::Checksum;
+use crate::collision::Hitbox;
+use crate::controls::MAX_PLAYERS;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
 use crate::wasm4::*;
 
+/// Each player wears a different mix of the palette.
+pub const PLAYER_COLORS: [u16; MAX_PLAYERS] = [0x4320, 0x4230, 0x2340, 0x3420];
+
+/// The feet of the little guy, which is where balls can hit.
+pub const HITBOX: Hitbox = Hitbox::rect(-4, -1, 8, 3);
+/// The space the little guy takes up, which must stay inside the arena.
+pub const FOOTPRINT: Hitbox = Hitbox::rect(-5, -3, 10, 6);
+
 pub struct Animation
 {
 	facing: Facing,
 	tag: AnimationTag,
 	frame: u8,
 	ticks: u8,
+	trail: (i32, i32),
 }
 
+#[derive(Clone, Copy)]
 enum Facing
 {
 	Left,
 	Right,
 }
 
+impl Facing
+{
+	const ALL: [Facing; 2] = [Facing::Left, Facing::Right];
+}
+
+#[derive(Clone, Copy)]
 enum AnimationTag
 {
 	IdleRight,
@@ -27,10 +48,37 @@ enum AnimationTag
 	RunRight,
 	RunLeft,
 	RunUp,
+	Dash,
+	Catch,
 	Death,
 	Gone,
 }
 
+impl AnimationTag
+{
+	const ALL: [AnimationTag; 9] = [
+		AnimationTag::IdleRight,
+		AnimationTag::IdleLeft,
+		AnimationTag::RunRight,
+		AnimationTag::RunLeft,
+		AnimationTag::RunUp,
+		AnimationTag::Dash,
+		AnimationTag::Catch,
+		AnimationTag::Death,
+		AnimationTag::Gone,
+	];
+
+	fn num_frames(self) -> u8
+	{
+		match self
+		{
+			AnimationTag::Dash | AnimationTag::Catch | AnimationTag::Gone => 1,
+			AnimationTag::Death => 6,
+			_ => 2,
+		}
+	}
+}
+
 impl Animation
 {
 	pub fn new() -> Self
@@ -40,6 +88,7 @@ impl Animation
 			tag: AnimationTag::IdleRight,
 			frame: 0,
 			ticks: 0,
+			trail: (0, 0),
 		}
 	}
 
@@ -146,12 +195,103 @@ impl Animation
 		}
 	}
 
+	/// Picks the animation for running in the given direction. Going up
+	/// shows the back of the little guy; any other direction shows a side.
+	pub fn run(&mut self, dx: i32, dy: i32)
+	{
+		if dy < 0
+		{
+			if dx < 0
+			{
+				self.facing = Facing::Left;
+			}
+			else if dx > 0
+			{
+				self.facing = Facing::Right;
+			}
+			self.run_up();
+		}
+		else if dx < 0
+		{
+			self.run_left();
+		}
+		else if dx > 0
+		{
+			self.run_right();
+		}
+		else if dy > 0
+		{
+			self.run_down();
+		}
+		else
+		{
+			self.idle();
+		}
+	}
+
+	/// Dashes in the given direction, leaving afterimages behind.
+	pub fn dash(&mut self, dx: i32, dy: i32)
+	{
+		if dx < 0
+		{
+			self.facing = Facing::Left;
+		}
+		else if dx > 0
+		{
+			self.facing = Facing::Right;
+		}
+		self.tag = AnimationTag::Dash;
+		self.frame = 0;
+		self.trail = (-dx.signum(), -dy.signum());
+	}
+
+	/// Holds out both arms until the little guy moves again.
+	pub fn catch(&mut self)
+	{
+		self.tag = AnimationTag::Catch;
+		self.frame = 0;
+	}
+
 	pub fn die(&mut self)
 	{
 		self.tag = AnimationTag::Death;
 		self.frame = 0;
 	}
 
+	pub fn hash_into(&self, checksum: &mut Checksum)
+	{
+		checksum.bytes(&[self.facing as u8, self.tag as u8, self.frame]);
+		checksum.bytes(&[self.ticks]);
+		checksum.i32(self.trail.0);
+		checksum.i32(self.trail.1);
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.bytes(&[self.facing as u8, self.tag as u8, self.frame]);
+		writer.u8(self.ticks);
+		writer.varint(self.trail.0);
+		writer.varint(self.trail.1);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let facing = *Facing::ALL.get(reader.u8()? as usize)?;
+		let tag = *AnimationTag::ALL.get(reader.u8()? as usize)?;
+		let frame = reader.u8()?;
+		if frame >= tag.num_frames()
+		{
+			return None;
+		}
+		Some(Self {
+			facing,
+			tag,
+			frame,
+			ticks: reader.u8()?,
+			trail: (reader.varint()?, reader.varint()?),
+		})
+	}
+
 	pub fn tick(&mut self)
 	{
 		self.ticks += 1;
@@ -169,11 +309,16 @@ impl Animation
 		self.frame += 1;
 		match self.tag
 		{
+			AnimationTag::Dash | AnimationTag::Catch | AnimationTag::Gone =>
+			{
+				self.frame = 0;
+			}
 			AnimationTag::Death =>
 			{
 				if self.frame >= 6
 				{
 					self.tag = AnimationTag::Gone;
+					self.frame = 0;
 				}
 			}
 			_ =>
@@ -187,7 +332,7 @@ impl Animation
 		}
 	}
 
-	pub fn draw(&self, x: i32, y: i32)
+	pub fn draw(&self, platform: &mut dyn Platform, x: i32, y: i32, colors: u16)
 	{
 		let start = match self.tag
 		{
@@ -196,16 +341,40 @@ impl Animation
 			AnimationTag::RunRight => 4,
 			AnimationTag::RunLeft => 6,
 			AnimationTag::RunUp => 8,
+			AnimationTag::Dash =>
+			{
+				// Mid-stride, with the legs stretched out.
+				let start = match self.facing
+				{
+					Facing::Left => 6,
+					Facing::Right => 4,
+				};
+				let frame = &LITTLE_GUY_FRAMES[start + 1];
+				platform.set_draw_colors(0x2220);
+				for distance in [8, 4]
+				{
+					platform.blit(
+						frame,
+						x - (LITTLE_GUY_WIDTH as i32) / 2
+							+ self.trail.0 * distance,
+						y - (LITTLE_GUY_HEIGHT as i32)
+							+ 2 + self.trail.1 * distance,
+						LITTLE_GUY_WIDTH,
+						LITTLE_GUY_HEIGHT,
+						BLIT_2BPP,
+					);
+				}
+				start + 1
+			}
+			AnimationTag::Catch => 16,
 			AnimationTag::Death => 10,
 			AnimationTag::Gone => return,
 		};
 		let offset: usize = start + (self.frame as usize);
 		let frame = &LITTLE_GUY_FRAMES[offset];
 
-		unsafe {
-			*DRAW_COLORS = 0x4320;
-		}
-		blit(
+		platform.set_draw_colors(colors);
+		platform.blit(
 			frame,
 			x - (LITTLE_GUY_WIDTH as i32) / 2,
 			y - (LITTLE_GUY_HEIGHT as i32) + 2,
@@ -218,7 +387,7 @@ impl Animation
 
 const LITTLE_GUY_WIDTH: u32 = 16;
 const LITTLE_GUY_HEIGHT: u32 = 16;
-const LITTLE_GUY_FRAMES: [[u8; 64]; 16] = [
+const LITTLE_GUY_FRAMES: [[u8; 64]; 17] = [
 	LITTLE_GUY1,
 	LITTLE_GUY2,
 	LITTLE_GUY3,
@@ -235,6 +404,7 @@ const LITTLE_GUY_FRAMES: [[u8; 64]; 16] = [
 	LITTLE_GUY14,
 	LITTLE_GUY15,
 	LITTLE_GUY16,
+	LITTLE_GUY_CATCH,
 ];
 
 // little_guy1
@@ -396,3 +566,13 @@ const LITTLE_GUY16: [u8; 64] = [
 	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
 	0x00, 0x00, 0x00, 0x00,
 ];
+
+/// Both arms up, ready to catch.
+const LITTLE_GUY_CATCH: [u8; 64] = [
+	0x00, 0x15, 0x54, 0x00, 0x00, 0x55, 0x55, 0x00, 0x10, 0x55, 0x55, 0x04,
+	0x14, 0x55, 0x55, 0x14, 0x05, 0x69, 0x69, 0x50, 0x00, 0x6d, 0x6d, 0x00,
+	0x00, 0x6d, 0x6d, 0x00, 0x00, 0x55, 0x55, 0x00, 0x00, 0x55, 0x55, 0x00,
+	0x00, 0x15, 0x54, 0x00, 0x00, 0x04, 0x10, 0x00, 0x00, 0x04, 0x10, 0x00,
+	0x00, 0x37, 0xdc, 0x00, 0x03, 0xf7, 0xdf, 0xc0, 0x03, 0xff, 0xff, 0xc0,
+	0x00, 0x3f, 0xfc, 0x00,
+];
@@ -5,6 +5,10 @@
 //
 
 pub mod ball;
+pub mod dither;
+pub mod heart;
 pub mod little_guy;
+pub mod warning_diagonal;
+pub mod warning_homing;
 pub mod warning_horizontal;
 pub mod warning_vertical;
@@ -0,0 +1,40 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::Platform;
+use crate::wasm4::*;
+
+/// Draws a `\` shaped warning, or a `/` shaped one if `is_flipped`.
+pub fn draw(platform: &mut dyn Platform, x: i32, y: i32, is_flipped: bool)
+{
+	platform.set_draw_colors(0x40);
+	let flags = if is_flipped
+	{
+		WARNING_DIAGONAL_FLAGS | BLIT_FLIP_X
+	}
+	else
+	{
+		WARNING_DIAGONAL_FLAGS
+	};
+	platform.blit(
+		&WARNING_DIAGONAL,
+		x - (WARNING_DIAGONAL_WIDTH as i32) / 2,
+		y - (WARNING_DIAGONAL_HEIGHT as i32) / 2,
+		WARNING_DIAGONAL_WIDTH,
+		WARNING_DIAGONAL_HEIGHT,
+		flags,
+	);
+}
+
+// warning_diagonal
+const WARNING_DIAGONAL_WIDTH: u32 = 16;
+const WARNING_DIAGONAL_HEIGHT: u32 = 16;
+const WARNING_DIAGONAL_FLAGS: u32 = 0; // BLIT_1BPP
+const WARNING_DIAGONAL: [u8; 32] = [
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x06, 0x00,
+	0x03, 0x00, 0x01, 0x80, 0x00, 0xc0, 0x00, 0x60, 0x00, 0x30, 0x00, 0x18,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
@@ -0,0 +1,30 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::Platform;
+
+pub fn draw(platform: &mut dyn Platform, x: i32, y: i32)
+{
+	platform.set_draw_colors(0x40);
+	platform.blit(
+		&WARNING_HOMING,
+		x - (WARNING_HOMING_WIDTH as i32) / 2,
+		y - (WARNING_HOMING_HEIGHT as i32) / 2,
+		WARNING_HOMING_WIDTH,
+		WARNING_HOMING_HEIGHT,
+		WARNING_HOMING_FLAGS,
+	);
+}
+
+// warning_homing
+const WARNING_HOMING_WIDTH: u32 = 16;
+const WARNING_HOMING_HEIGHT: u32 = 16;
+const WARNING_HOMING_FLAGS: u32 = 0; // BLIT_1BPP
+const WARNING_HOMING: [u8; 32] = [
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xc0, 0x04, 0x20,
+	0x08, 0x10, 0x09, 0x90, 0x09, 0x90, 0x08, 0x10, 0x04, 0x20, 0x03, 0xc0,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
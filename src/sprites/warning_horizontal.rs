@@ -4,14 +4,12 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::Platform;
 
-pub fn draw(x: i32, y: i32)
+pub fn draw(platform: &mut dyn Platform, x: i32, y: i32)
 {
-	unsafe {
-		*DRAW_COLORS = 0x40;
-	}
-	blit(
+	platform.set_draw_colors(0x40);
+	platform.blit(
 		&WARNING_HORIZONTAL,
 		x - (WARNING_HORIZONTAL_WIDTH as i32) / 2,
 		y - (WARNING_HORIZONTAL_HEIGHT as i32) / 2,
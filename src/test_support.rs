@@ -0,0 +1,75 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Helpers shared by the tests of several modules.
+
+use crate::headless::Headless;
+use crate::pause::{PAUSE_COMBO, PAUSE_HOLD_TIME};
+use crate::wasm4::*;
+use crate::{Game, FADE_TIME};
+
+/// Walking in circles survives for a while, but not forever.
+pub fn wander(frame: usize) -> u8
+{
+	let directions = [BUTTON_LEFT, BUTTON_UP, BUTTON_RIGHT, BUTTON_DOWN];
+	directions[(frame / 37) % 4]
+}
+
+/// A random gamepad state, which never holds the full pause combo.
+pub fn random_gamepad(rng: &fastrand::Rng) -> u8
+{
+	let gamepad = rng.u8(..);
+	if gamepad & PAUSE_COMBO != PAUSE_COMBO
+	{
+		gamepad
+	}
+	else if rng.bool()
+	{
+		gamepad & !BUTTON_1
+	}
+	else
+	{
+		gamepad & !BUTTON_2
+	}
+}
+
+pub fn press(game: &mut Game, console: &mut Headless, gamepad: u8)
+{
+	console.gamepads[0] = gamepad;
+	game.update(console);
+	console.gamepads[0] = 0;
+	game.update(console);
+}
+
+/// Holds the pause combo until the pause opens, then lets go.
+pub fn pause(game: &mut Game, console: &mut Headless)
+{
+	console.gamepads[0] = PAUSE_COMBO;
+	for _ in 0..=PAUSE_HOLD_TIME
+	{
+		game.update(console);
+	}
+	console.gamepads[0] = 0;
+	game.update(console);
+}
+
+pub fn wait_for_fade(game: &mut Game, console: &mut Headless)
+{
+	for _ in 0..(2 * FADE_TIME)
+	{
+		game.update(console);
+	}
+	assert!(game.fade.is_none());
+}
+
+/// Starts a run on the preset that is selected by default.
+pub fn start_run(game: &mut Game, console: &mut Headless)
+{
+	game.start(console);
+	press(game, console, BUTTON_1);
+	press(game, console, BUTTON_1);
+	wait_for_fade(game, console);
+}
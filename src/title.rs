@@ -0,0 +1,124 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::challenge::Challenge;
+use crate::controls::Input;
+use crate::leaderboard::Leaderboard;
+use crate::level::Level;
+use crate::menu::{self, List};
+use crate::mode_select::ModeSelect;
+use crate::options::Options;
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::versus::VersusSelect;
+use crate::{Context, Scene, Transition};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Choice
+{
+	Start,
+	Practice,
+	Challenge,
+	Versus,
+	Watch,
+	HighScores,
+	Options,
+}
+
+impl Choice
+{
+	const ALL: [Choice; 7] = [
+		Choice::Start,
+		Choice::Practice,
+		Choice::Challenge,
+		Choice::Versus,
+		Choice::Watch,
+		Choice::HighScores,
+		Choice::Options,
+	];
+
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			Choice::Start => "START",
+			Choice::Practice => "PRACTICE",
+			Choice::Challenge => "CHALLENGE",
+			Choice::Versus => "VERSUS",
+			Choice::Watch => "WATCH REPLAY",
+			Choice::HighScores => "HIGH SCORES",
+			Choice::Options => "OPTIONS",
+		}
+	}
+}
+
+pub struct Title
+{
+	list: List,
+}
+
+impl Title
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			list: List::new(Choice::ALL.len()),
+		}
+	}
+
+	pub fn update(
+		&mut self,
+		input: Input,
+		context: &Context,
+	) -> Option<Transition>
+	{
+		let choice = Choice::ALL[self.list.update(input.pressed)?];
+		let scene = match choice
+		{
+			Choice::Start => Scene::ModeSelect(ModeSelect::new()),
+			Choice::Practice => Scene::ModeSelect(ModeSelect::practice()),
+			Choice::Challenge => Scene::Challenge(Challenge::new()),
+			Choice::Versus => Scene::VersusSelect(VersusSelect::new()),
+			Choice::Watch =>
+			{
+				// Without a saved replay there is nothing to watch.
+				let recording = context.save_data.replay.as_ref()?;
+				let level = Level::replay(recording);
+				let watch = Transition::Push(Scene::Level(Box::new(level)));
+				return Some(Transition::Fade(Box::new(watch)));
+			}
+			Choice::HighScores =>
+			{
+				Scene::HighScores(Leaderboard::new(context.settings.preset))
+			}
+			Choice::Options => Scene::Options(Options::new()),
+		};
+		Some(Transition::Push(scene))
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		self.list.write(writer);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let list = List::read(reader, Choice::ALL.len())?;
+		Some(Self { list })
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, "DODGEBALL", 36);
+
+		let names = Choice::ALL.map(Choice::name);
+		self.list.draw(platform, &names, 44, 72);
+	}
+}
@@ -0,0 +1,446 @@
+//
+// Part of dodgeball-wasm-4
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::controls::{Input, Movement, MAX_PLAYERS};
+use crate::level::Level;
+use crate::menu;
+use crate::palette;
+use crate::platform::Platform;
+use crate::save::{Reader, Writer};
+use crate::seed_code;
+use crate::sprites;
+use crate::title::Title;
+use crate::wasm4::*;
+use crate::{Context, Scene, Transition};
+
+/// The longest match that can be picked, in rounds.
+pub const MAX_BEST_OF: i32 = 5;
+
+/// Every way to split up the players: how many there are and whether they
+/// play in two teams on a split court.
+const FORMATS: [(usize, bool); 5] =
+	[(2, false), (3, false), (4, false), (2, true), (4, true)];
+
+/// How a match between players who throw balls at each other stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Standings
+{
+	pub num_players: usize,
+	/// Whether the even and odd players form teams on either side of the
+	/// court, rather than every player being on their own.
+	pub is_teams: bool,
+	/// Whoever wins the majority of this many rounds wins the match.
+	pub best_of: i32,
+	/// Rounds won by each side.
+	pub wins: [i32; MAX_PLAYERS],
+	pub rounds_played: i32,
+}
+
+impl Standings
+{
+	pub const fn new(num_players: usize, is_teams: bool, best_of: i32) -> Self
+	{
+		Self {
+			num_players,
+			is_teams,
+			best_of,
+			wins: [0; MAX_PLAYERS],
+			rounds_played: 0,
+		}
+	}
+
+	pub fn num_sides(&self) -> usize
+	{
+		if self.is_teams
+		{
+			2
+		}
+		else
+		{
+			self.num_players
+		}
+	}
+
+	/// Which side the player is on, left or right in a team match.
+	pub fn side(&self, player: usize) -> usize
+	{
+		if self.is_teams
+		{
+			player % 2
+		}
+		else
+		{
+			player
+		}
+	}
+
+	pub fn side_name(&self, side: usize) -> String
+	{
+		if self.is_teams
+		{
+			["LEFT", "RIGHT"][side].to_string()
+		}
+		else
+		{
+			format!("P{}", side + 1)
+		}
+	}
+
+	pub fn wins_needed(&self) -> i32
+	{
+		self.best_of / 2 + 1
+	}
+
+	/// A round in which nobody survived has no winner.
+	pub fn record(&mut self, winner: Option<usize>)
+	{
+		self.rounds_played += 1;
+		if let Some(side) = winner
+		{
+			self.wins[side] += 1;
+		}
+	}
+
+	pub fn champion(&self) -> Option<usize>
+	{
+		(0..self.num_sides()).find(|&s| self.wins[s] >= self.wins_needed())
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		writer.u8(self.num_players as u8);
+		writer.bool(self.is_teams);
+		writer.varint(self.best_of);
+		for wins in self.wins
+		{
+			writer.varint(wins);
+		}
+		writer.varint(self.rounds_played);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let num_players = reader.u8()? as usize;
+		let is_teams = reader.bool()?;
+		let best_of = reader.varint()?;
+		if !(1..=MAX_PLAYERS).contains(&num_players)
+			|| !(1..=MAX_BEST_OF).contains(&best_of)
+		{
+			return None;
+		}
+		let mut wins = [0; MAX_PLAYERS];
+		for side_wins in &mut wins
+		{
+			*side_wins = reader.varint()?;
+		}
+		Some(Self {
+			num_players,
+			is_teams,
+			best_of,
+			wins,
+			rounds_played: reader.varint()?,
+		})
+	}
+}
+
+/// Picks how many players take part and how long the match lasts.
+pub struct VersusSelect;
+
+impl VersusSelect
+{
+	pub const fn new() -> Self
+	{
+		Self
+	}
+
+	pub fn update(
+		&mut self,
+		input: Input,
+		context: &mut Context,
+	) -> Option<Transition>
+	{
+		let settings = &mut context.settings;
+		if input.pressed & BUTTON_LEFT != 0
+		{
+			settings.best_of = if settings.best_of > 1
+			{
+				settings.best_of - 2
+			}
+			else
+			{
+				MAX_BEST_OF
+			};
+		}
+		else if input.pressed & BUTTON_RIGHT != 0
+		{
+			settings.best_of = if settings.best_of < MAX_BEST_OF
+			{
+				settings.best_of + 2
+			}
+			else
+			{
+				1
+			};
+		}
+		else if input.pressed & (BUTTON_UP | BUTTON_DOWN) != 0
+		{
+			let format = (settings.num_rivals, settings.is_teams);
+			let current = FORMATS.iter().position(|&x| x == format);
+			let step = if input.pressed & BUTTON_UP != 0
+			{
+				1
+			}
+			else
+			{
+				-1
+			};
+			let i = current.unwrap_or(0) as i32 + step;
+			let (num_rivals, is_teams) =
+				FORMATS[i.rem_euclid(FORMATS.len() as i32) as usize];
+			settings.num_rivals = num_rivals;
+			settings.is_teams = is_teams;
+		}
+
+		if input.pressed & BUTTON_1 != 0
+		{
+			let seed = seed_code::truncate(context.ticks);
+			let standings = Standings::new(
+				settings.num_rivals,
+				settings.is_teams,
+				settings.best_of,
+			);
+			let level = Level::versus(seed, settings.movement, standings);
+			let start = Transition::Replace(Scene::Level(Box::new(level)));
+			Some(Transition::Fade(Box::new(start)))
+		}
+		else if input.pressed & BUTTON_2 != 0
+		{
+			Some(Transition::Pop)
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform, context: &Context)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		platform.set_draw_colors(4);
+		menu::draw_centered(platform, "VERSUS", 10);
+		platform.text("Z: GRAB OR THROW", 16, 34);
+		platform.text("X: DASH", 16, 44);
+
+		let settings = &context.settings;
+		if settings.is_teams
+		{
+			platform.text("A HIT SENDS RIVALS", 8, 62);
+			platform.text("TO JAIL UNTIL THEY", 8, 72);
+			platform.text("HIT SOMEONE BACK", 16, 82);
+		}
+		else
+		{
+			platform.text("LAST ONE STANDING", 12, 62);
+			platform.text("WINS THE ROUND", 24, 72);
+		}
+
+		let best_of = format!("BEST OF {}", settings.best_of);
+		menu::draw_carousel(platform, &best_of, 100);
+		let players = if settings.is_teams
+		{
+			let team_size = settings.num_rivals / 2;
+			format!("TEAMS {} VS {}", team_size, team_size)
+		}
+		else
+		{
+			format!("{} PLAYERS", settings.num_rivals)
+		};
+		menu::draw_centered(platform, &players, 112);
+
+		platform.text("PRESS X TO START", 10, 130);
+		platform.text("PRESS Z TO GO BACK", 10, 140);
+	}
+}
+
+/// Shown between rounds, and once more when someone has won the match.
+pub struct Scoreboard
+{
+	standings: Standings,
+	winner: Option<usize>,
+	/// Where the random number generator left off, to seed the next round.
+	next_seed: u64,
+	movement: Movement,
+}
+
+impl Scoreboard
+{
+	pub fn new(
+		standings: Standings,
+		winner: Option<usize>,
+		next_seed: u64,
+		movement: Movement,
+	) -> Self
+	{
+		Self {
+			standings,
+			winner,
+			next_seed,
+			movement,
+		}
+	}
+
+	pub fn update(&mut self, input: Input) -> Option<Transition>
+	{
+		if input.pressed & BUTTON_1 != 0
+		{
+			let standings = match self.standings.champion()
+			{
+				Some(_) => Standings {
+					wins: [0; MAX_PLAYERS],
+					rounds_played: 0,
+					..self.standings
+				},
+				None => self.standings,
+			};
+			let level = Level::versus(self.next_seed, self.movement, standings);
+			Some(Transition::Replace(Scene::Level(Box::new(level))))
+		}
+		else if input.pressed & BUTTON_2 != 0
+		{
+			let quit = Transition::Reset(Scene::Title(Title::new()));
+			Some(Transition::Fade(Box::new(quit)))
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn write(&self, writer: &mut Writer)
+	{
+		self.standings.write(writer);
+		writer.option(self.winner, |writer, side| writer.u8(side as u8));
+		writer.u64(self.next_seed);
+		writer.u8(self.movement.index() as u8);
+	}
+
+	pub fn read(reader: &mut Reader) -> Option<Self>
+	{
+		let standings = Standings::read(reader)?;
+		let winner = reader.option(|reader| reader.u8())?.map(usize::from);
+		if winner.is_some_and(|side| side >= standings.num_sides())
+		{
+			return None;
+		}
+		Some(Self {
+			standings,
+			winner,
+			next_seed: reader.u64()?,
+			movement: Movement::from_index(reader.u8()? as usize)?,
+		})
+	}
+
+	pub fn draw(&self, platform: &mut dyn Platform)
+	{
+		platform.set_palette(palette::SODACAP_PALETTE);
+
+		let standings = &self.standings;
+		let champion = standings.champion();
+		platform.set_draw_colors(4);
+		let heading = match champion
+		{
+			Some(_) => "MATCH OVER".to_string(),
+			None => format!("ROUND {}", standings.rounds_played),
+		};
+		menu::draw_centered(platform, &heading, 10);
+
+		platform.set_draw_colors(2);
+		let result = match (champion, self.winner)
+		{
+			(Some(side), _) =>
+			{
+				format!("{} WINS THE MATCH", standings.side_name(side))
+			}
+			(None, Some(side)) =>
+			{
+				format!("{} WINS THE ROUND", standings.side_name(side))
+			}
+			(None, None) => "NOBODY SURVIVED".to_string(),
+		};
+		menu::draw_centered(platform, &result, 26);
+
+		for side in 0..standings.num_sides()
+		{
+			let y = 48 + 18 * (side as i32);
+			let members = (0..standings.num_players)
+				.filter(|&player| standings.side(player) == side);
+			for (i, player) in members.enumerate()
+			{
+				let colors = sprites::little_guy::PLAYER_COLORS[player];
+				let x = 32 - 12 * (i as i32);
+				sprites::little_guy::Animation::new().draw(
+					platform,
+					x,
+					y + 10,
+					colors,
+				);
+			}
+			platform.set_draw_colors(4);
+			platform.text(&standings.side_name(side), 44, y);
+			for i in 0..standings.wins_needed()
+			{
+				let is_won = i < standings.wins[side];
+				platform.set_draw_colors(if is_won { 0x44 } else { 0x40 });
+				platform.rect(92 + 12 * i, y, 8, 8);
+			}
+		}
+
+		platform.set_draw_colors(4);
+		if champion.is_some()
+		{
+			platform.text("PRESS X FOR REMATCH", 4, 130);
+		}
+		else
+		{
+			platform.text("PRESS X TO GO ON", 16, 130);
+		}
+		platform.text("PRESS Z TO QUIT", 20, 140);
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn test_majority_wins_the_match()
+	{
+		let mut standings = Standings::new(3, false, 3);
+		assert_eq!(standings.wins_needed(), 2);
+		standings.record(Some(2));
+		standings.record(None);
+		standings.record(Some(0));
+		assert_eq!(standings.champion(), None);
+		standings.record(Some(2));
+		assert_eq!(standings.champion(), Some(2));
+		assert_eq!(standings.rounds_played, 4);
+		assert_eq!(standings.wins, [1, 0, 2, 0]);
+	}
+
+	#[test]
+	fn test_teams_share_their_wins()
+	{
+		let mut standings = Standings::new(4, true, 1);
+		assert_eq!(standings.num_sides(), 2);
+		assert_eq!(standings.side(2), standings.side(0));
+		assert_ne!(standings.side(3), standings.side(2));
+		standings.record(Some(standings.side(3)));
+		assert_eq!(standings.champion(), Some(1));
+		assert_eq!(standings.side_name(1), "RIGHT");
+	}
+}